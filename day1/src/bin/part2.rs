@@ -1,57 +1,291 @@
+use std::collections::HashMap;
+
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part2(input);
-    println!("Result: {}", result);
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    // Optionally pass a vocabulary config file, otherwise the English digit words are used
+    let vocab_path = args.iter().find(|a| !a.starts_with("--"));
+    let vocab = match vocab_path {
+        Some(path) => Vocabulary::load(path),
+        None => Vocabulary::english(),
+    };
+    match args.iter().find_map(|a| a.strip_prefix("--diagnostics=")) {
+        Some("table") => println!("{}", diagnostics_table(input, &vocab)),
+        Some("json") => println!("{}", diagnostics_json(input, &vocab)),
+        Some(other) => panic!("Invalid diagnostics format: {}", other),
+        None => {
+            let result: String = match vocab_path {
+                Some(_) => calibrate(input, &vocab),
+                None => part2(input),
+            };
+            println!("Result: {}", result);
+        }
+    }
 }
 
-fn word_to_num(word: &str) -> Option<u32> {
-    match word {
-        "one" => Some(1),
-        "two" => Some(2),
-        "three" => Some(3),
-        "four" => Some(4),
-        "five" => Some(5),
-        "six" => Some(6),
-        "seven" => Some(7),
-        "eight" => Some(8),
-        "nine" => Some(9),
-        "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9" => {
-            Some(word.parse::<u32>().unwrap())
+#[derive(Debug, Default)]
+struct TrieNode {
+    children: HashMap<char, usize>,
+    value: Option<u32>,
+}
+
+#[derive(Debug)]
+struct Trie {
+    nodes: Vec<TrieNode>, // nodes[0] is the root
+}
+
+impl Trie {
+    fn new() -> Trie {
+        Trie {
+            nodes: vec![TrieNode::default()],
         }
-        _ => None,
     }
-}
 
-fn part2(input: &str) -> String {
-    let items = input.split("\n");
-    let numbers = [
-        "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
-    ];
+    fn insert(&mut self, word: &str, value: u32) {
+        let mut curr = 0;
+        for c in word.chars() {
+            curr = match self.nodes[curr].children.get(&c) {
+                Some(&next) => next,
+                None => {
+                    self.nodes.push(TrieNode::default());
+                    let next = self.nodes.len() - 1;
+                    self.nodes[curr].children.insert(c, next);
+                    next
+                }
+            };
+        }
+        self.nodes[curr].value = Some(value);
+    }
 
-    let mut sum: u32 = 0;
-    for item in items {
-        let mut curr = Vec::new();
-        for (index, i) in item.chars().enumerate() {
-            if i.is_digit(10) {
-                curr.push([i.to_digit(10).unwrap(), index.try_into().unwrap()]);
+    // Byte length and value of the longest word starting at the beginning of text
+    fn longest_prefix(&self, text: &str) -> Option<(usize, u32)> {
+        let mut curr = 0;
+        let mut res = None;
+        for (i, c) in text.char_indices() {
+            match self.nodes[curr].children.get(&c) {
+                Some(&next) => curr = next,
+                None => break,
+            }
+            if let Some(value) = self.nodes[curr].value {
+                res = Some((i + c.len_utf8(), value));
             }
         }
-        for num in numbers {
-            if item.contains(num) {
-                let indices = item.match_indices(num);
-                for (index, _) in indices {
-                    curr.push([word_to_num(num).unwrap(), index.try_into().unwrap()]);
-                }
+        res
+    }
+
+    // Longest word at every char offset. Each offset is tried on its own so overlapping words like "eightwo" both match
+    fn tokens<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Token<'a>> + 'a {
+        line.char_indices().filter_map(|(offset, _)| {
+            self.longest_prefix(&line[offset..])
+                .map(|(len, value)| Token {
+                    offset,
+                    text: &line[offset..offset + len],
+                    value,
+                })
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Token<'a> {
+    offset: usize,
+    text: &'a str,
+    value: u32,
+}
+
+#[derive(Debug)]
+struct Vocabulary {
+    trie: Trie,
+}
+
+impl Vocabulary {
+    fn new(words: &[(&str, u32)]) -> Vocabulary {
+        let mut vocab = Vocabulary { trie: Trie::new() };
+        for (word, value) in words {
+            vocab.insert(word, *value);
+        }
+        vocab
+    }
+
+    // Calibration values are first * 10 + last so every word has to stand for a single digit
+    fn insert(&mut self, word: &str, value: u32) {
+        if value > 9 {
+            panic!(
+                "Vocabulary value for {} is not a single digit: {}",
+                word, value
+            );
+        }
+        self.trie.insert(word, value);
+    }
+
+    // "one".."nine" and the digits themselves
+    fn english() -> Vocabulary {
+        let words = [
+            "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+        ];
+        let mut vocab = Vocabulary::new(&[]);
+        for (i, word) in words.iter().enumerate() {
+            vocab.insert(word, i as u32 + 1);
+        }
+        vocab.add_digits();
+        vocab
+    }
+
+    fn add_digits(&mut self) {
+        for d in 0..10 {
+            self.insert(&d.to_string(), d);
+        }
+    }
+
+    // One `word = value` per line. Blank lines and lines starting with # are ignored and
+    // a `digits` line also accepts the plain digits 0-9
+    fn from_config(config: &str) -> Vocabulary {
+        let mut vocab = Vocabulary::new(&[]);
+        for line in config.lines().map(|l| l.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
+            if line == "digits" {
+                vocab.add_digits();
+                continue;
+            }
+            let (word, value) = line
+                .split_once('=')
+                .unwrap_or_else(|| panic!("Invalid vocabulary line: {}", line));
+            let value = value
+                .trim()
+                .parse::<u32>()
+                .unwrap_or_else(|_| panic!("Invalid vocabulary value: {}", line));
+            vocab.insert(word.trim(), value);
         }
+        vocab
+    }
 
-        if curr.len() == 0 {
-            continue;
+    fn load(path: &str) -> Vocabulary {
+        let config = std::fs::read_to_string(path)
+            .unwrap_or_else(|_| panic!("Could not read vocabulary: {}", path));
+        Vocabulary::from_config(&config)
+    }
+
+    fn tokens<'a>(&'a self, line: &'a str) -> impl Iterator<Item = Token<'a>> + 'a {
+        self.trie.tokens(line)
+    }
+
+    // Every (byte offset, value) hit in the line
+    fn matches<'a>(&'a self, line: &'a str) -> impl Iterator<Item = (usize, u32)> + 'a {
+        self.tokens(line).map(|t| (t.offset, t.value))
+    }
+}
+
+#[derive(Debug)]
+struct LineDiagnostic<'a> {
+    line: usize, // 1-indexed line number
+    first: Option<Token<'a>>,
+    last: Option<Token<'a>>,
+}
+
+impl LineDiagnostic<'_> {
+    fn value(&self) -> u32 {
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => first.value * 10 + last.value,
+            _ => 0,
         }
-        curr.sort_by(|a, b| a[1].cmp(&b[1]));
-        sum += curr[0][0] * 10 + curr.last().unwrap()[0];
     }
-    return sum.to_string();
+
+    fn no_match(&self) -> bool {
+        self.first.is_none()
+    }
+
+    fn to_table_row(&self) -> String {
+        let fmt = |t: Option<Token>| match t {
+            Some(t) => format!("{}@{}", t.text, t.offset),
+            None => "-".to_string(),
+        };
+        format!(
+            "{:>5} {:>12} {:>12} {:>5} {}",
+            self.line,
+            fmt(self.first),
+            fmt(self.last),
+            self.value(),
+            if self.no_match() { "NO MATCH" } else { "" }
+        )
+        .trim_end()
+        .to_string()
+    }
+
+    fn to_json(&self) -> String {
+        let fmt = |t: Option<Token>| match t {
+            Some(t) => format!(
+                "{{\"text\":\"{}\",\"offset\":{},\"value\":{}}}",
+                t.text.replace('\\', "\\\\").replace('"', "\\\""),
+                t.offset,
+                t.value
+            ),
+            None => "null".to_string(),
+        };
+        format!(
+            "{{\"line\":{},\"first\":{},\"last\":{},\"value\":{},\"no_match\":{}}}",
+            self.line,
+            fmt(self.first),
+            fmt(self.last),
+            self.value(),
+            self.no_match()
+        )
+    }
+}
+
+fn diagnose<'a>(input: &'a str, vocab: &'a Vocabulary) -> Vec<LineDiagnostic<'a>> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, item)| {
+            let mut tokens = vocab.tokens(item);
+            let first = tokens.next();
+            let last = tokens.last().or(first);
+            LineDiagnostic {
+                line: i + 1,
+                first,
+                last,
+            }
+        })
+        .collect()
+}
+
+fn diagnostics_table(input: &str, vocab: &Vocabulary) -> String {
+    let mut rows = vec![format!(
+        "{:>5} {:>12} {:>12} {:>5}",
+        "line", "first", "last", "value"
+    )];
+    rows.extend(diagnose(input, vocab).iter().map(|d| d.to_table_row()));
+    rows.join("\n")
+}
+
+fn diagnostics_json(input: &str, vocab: &Vocabulary) -> String {
+    let rows = diagnose(input, vocab)
+        .iter()
+        .map(|d| d.to_json())
+        .collect::<Vec<String>>();
+    format!("[{}]", rows.join(","))
+}
+
+fn part2(input: &str) -> String {
+    calibrate(input, &Vocabulary::english())
+}
+
+fn calibrate(input: &str, vocab: &Vocabulary) -> String {
+    let items = input.split("\n");
+
+    let mut sum: u32 = 0;
+    for item in items {
+        let mut matches = vocab.matches(item);
+        let first = match matches.next() {
+            Some((_, v)) => v,
+            None => continue,
+        };
+        let last = matches.last().map(|(_, v)| v).unwrap_or(first);
+        sum += first * 10 + last;
+    }
+    sum.to_string()
 }
 
 #[cfg(test)]
@@ -72,4 +306,56 @@ zoneight234
         let result: String = part2(test_input);
         assert_eq!(result, "281".to_string());
     }
+
+    #[test]
+    fn overlapping_words() {
+        let vocab = Vocabulary::english();
+        let hits = vocab.matches("xeightwone7").collect::<Vec<_>>();
+        assert_eq!(hits, vec![(1, 8), (5, 2), (7, 1), (10, 7)]);
+        assert_eq!(part2("eightwo\ntwone"), "103".to_string());
+    }
+
+    #[test]
+    fn custom_vocabulary() {
+        let french = Vocabulary::from_config(
+            "# French
+un = 1
+deux = 2
+trois = 3
+huit = 8
+digits",
+        );
+        assert_eq!(calibrate("xdeuxhuit\ntroisun4", &french), "62".to_string());
+
+        // Longest match wins at each offset so IV reads as 4 rather than 1
+        let roman = Vocabulary::new(&[("I", 1), ("IV", 4), ("V", 5), ("IX", 9)]);
+        let hits = roman.matches("aIVb").collect::<Vec<_>>();
+        assert_eq!(hits, vec![(1, 4), (2, 5)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "not a single digit")]
+    fn multi_digit_value() {
+        Vocabulary::from_config("X = 10");
+    }
+
+    #[test]
+    fn diagnostics() {
+        let vocab = Vocabulary::english();
+        let diags = diagnose("abc\nzoneight234", &vocab);
+        assert!(diags[0].no_match());
+        assert_eq!(diags[0].value(), 0);
+        assert_eq!(diags[1].first.unwrap().text, "one");
+        assert_eq!(diags[1].last.unwrap().offset, 10);
+        assert_eq!(diags[1].value(), 14);
+        // A trailing newline is not an extra line and CRLF endings are stripped
+        let diags = diagnose("two1nine\r\nabc1\n", &vocab);
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].last.unwrap().text, "nine");
+        assert!(diags.iter().all(|d| !d.no_match()));
+        assert_eq!(
+            diagnostics_json("7", &vocab),
+            r#"[{"line":1,"first":{"text":"7","offset":0,"value":7},"last":{"text":"7","offset":0,"value":7},"value":77,"no_match":false}]"#
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Copy)]
 enum Direction {
@@ -18,25 +18,61 @@ struct Cell {
     directions: [Direction; 2],
 }
 
-impl Cell {
-    fn get_neighbour_coords(&self) -> Vec<(i32, i32)> {
-        let directions = &self.directions;
-        let mut coords = HashSet::new();
-
-        directions.iter().for_each(|x| match x {
-            Direction::Up => _ = coords.insert((self.x, self.y - 1)),
-            Direction::Down => _ = coords.insert((self.x, self.y + 1)),
-            Direction::Left => _ = coords.insert((self.x - 1, self.y)),
-            Direction::Right => _ = coords.insert((self.x + 1, self.y)),
-            Direction::Any => {
-                _ = coords.insert((self.x, self.y - 1));
-                _ = coords.insert((self.x, self.y + 1));
-                _ = coords.insert((self.x - 1, self.y));
-                _ = coords.insert((self.x + 1, self.y));
-            }
-            Direction::NA => (),
-        });
-        coords.into_iter().collect::<Vec<(i32, i32)>>()
+impl Direction {
+    fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            _ => panic!("{:?} is not a single direction", self),
+        }
+    }
+
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            other => *other,
+        }
+    }
+}
+
+// Follow the pipe from the start until it comes back, returning every loop cell in order.
+// Pipes next to S can point into it without being on the loop so each way out is tried
+fn trace_loop(map: &HashMap<(i32, i32), Cell>, start: &Cell) -> Vec<(i32, i32)> {
+    [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ]
+    .iter()
+    .find_map(|heading| trace_from(map, start, *heading))
+    .expect("S is on a loop")
+}
+
+// None if the pipe leaves the map or runs into something it does not connect to
+fn trace_from(
+    map: &HashMap<(i32, i32), Cell>,
+    start: &Cell,
+    mut heading: Direction,
+) -> Option<Vec<(i32, i32)>> {
+    let mut vertices = vec![(start.x, start.y)];
+    loop {
+        let (x, y) = *vertices.last().unwrap();
+        let (dx, dy) = heading.offset();
+        let next = map.get(&(x + dx, y + dy))?;
+        if next.val == 'S' {
+            return Some(vertices);
+        }
+        if !next.directions.contains(&heading.opposite()) {
+            return None;
+        }
+        vertices.push((next.x, next.y));
+        heading = *next.directions.iter().find(|d| **d != heading.opposite())?;
     }
 }
 
@@ -48,30 +84,8 @@ fn main() {
 
 fn part1(input: &str) -> String {
     let (map, starting_cell) = parse(input);
-    let mut visited: HashSet<Cell> = HashSet::new();
-    let mut queue: VecDeque<_> = [(0, starting_cell)].into();
-
-    let mut c = 0;
-
-    while !queue.is_empty() {
-        let (dist, curr) = queue.pop_front().expect("queue not empty");
-        visited.insert(curr);
-
-        if curr.val == '.' {
-            continue;
-        }
-
-        let neighbours = curr.get_neighbour_coords();
-        neighbours.iter().for_each(|x| {
-            let next_cell = map.get(&(x.0, x.1));
-            if next_cell.is_some() && !visited.contains(&next_cell.unwrap()) {
-                queue.push_back((dist + 1, *next_cell.unwrap()));
-            }
-        });
-
-        c = c.max(dist);
-    }
-    c.to_string()
+    let vertices = trace_loop(&map, &starting_cell);
+    (vertices.len() / 2).to_string()
 }
 
 fn parse(input: &str) -> (HashMap<(i32, i32), Cell>, Cell) {
@@ -187,4 +201,16 @@ LJ...";
         let result: String = part1(test_input);
         assert_eq!(result, "8".to_string());
     }
+
+    #[test]
+    fn dead_end_into_start() {
+        // The pipe above S points into it but is not part of the loop
+        let test_input: &str = ".|...
+.S-7.
+.|.|.
+.L-J.
+.....";
+        let result: String = part1(test_input);
+        assert_eq!(result, "4".to_string());
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq, Copy)]
 enum Direction {
@@ -18,126 +18,89 @@ struct Cell {
     directions: [Direction; 2],
 }
 
-impl Cell {
-    fn get_neighbour_coords(&self) -> Vec<(i32, i32)> {
-        let directions = &self.directions;
-        let mut coords = HashSet::new();
-
-        directions.iter().for_each(|x| match x {
-            Direction::Up => _ = coords.insert((self.x, self.y - 1)),
-            Direction::Down => _ = coords.insert((self.x, self.y + 1)),
-            Direction::Left => _ = coords.insert((self.x - 1, self.y)),
-            Direction::Right => _ = coords.insert((self.x + 1, self.y)),
-            Direction::Any => {
-                _ = coords.insert((self.x, self.y - 1));
-                _ = coords.insert((self.x, self.y + 1));
-                _ = coords.insert((self.x - 1, self.y));
-                _ = coords.insert((self.x + 1, self.y));
-            }
-            Direction::NA => (),
-        });
-        coords.into_iter().collect::<Vec<(i32, i32)>>()
+impl Direction {
+    fn offset(&self) -> (i32, i32) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+            _ => panic!("{:?} is not a single direction", self),
+        }
     }
-}
-
-fn main() {
-    let input: &str = include_str!("./input.txt");
-    let result: String = part2(input);
-    println!("Result: {}", result);
-}
 
-fn part2(input: &str) -> String {
-    let width = input.lines().next().unwrap().len();
-    let height = input.lines().count();
-    let (map, starting_cell) = parse(input);
-    let mut visited: HashSet<Cell> = HashSet::new();
-    let mut predescessors: HashMap<Cell, Cell> = HashMap::new();
-    let mut queue: VecDeque<_> = [(0, starting_cell)].into();
-
-    let mut furthest_dist = 0;
-    let mut last = starting_cell;
-    while !queue.is_empty() {
-        let (dist, curr) = queue.pop_front().expect("queue not empty");
-        visited.insert(curr);
-
-        if curr.val == '.' {
-            continue;
+    fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            other => *other,
         }
-        last = curr;
-
-        let neighbours = curr.get_neighbour_coords();
-        neighbours.iter().for_each(|x| {
-            let next_cell = map.get(&(x.0, x.1));
-            if next_cell.is_some() && !visited.contains(&next_cell.unwrap()) {
-                queue.push_back((dist + 1, *next_cell.unwrap()));
-                predescessors.insert(*next_cell.unwrap(), curr);
-            }
-        });
-
-        furthest_dist = furthest_dist.max(dist);
     }
+}
 
-    let mut path = Vec::new();
-    while last.val != 'S' {
-        let p = predescessors.get(&last).unwrap().clone();
-        path.push(p);
-        last = p;
-    }
-    path = path.into_iter().rev().collect::<Vec<_>>();
+// Follow the pipe from the start until it comes back, returning every loop cell in order.
+// Pipes next to S can point into it without being on the loop so each way out is tried
+fn trace_loop(map: &HashMap<(i32, i32), Cell>, start: &Cell) -> Vec<(i32, i32)> {
+    [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ]
+    .iter()
+    .find_map(|heading| trace_from(map, start, *heading))
+    .expect("S is on a loop")
+}
 
+// None if the pipe leaves the map or runs into something it does not connect to
+fn trace_from(
+    map: &HashMap<(i32, i32), Cell>,
+    start: &Cell,
+    mut heading: Direction,
+) -> Option<Vec<(i32, i32)>> {
+    let mut vertices = vec![(start.x, start.y)];
     loop {
-        if path.iter().filter(|x| x.val == 'S').count() == 2 {
-            break;
+        let (x, y) = *vertices.last().unwrap();
+        let (dx, dy) = heading.offset();
+        let next = map.get(&(x + dx, y + dy))?;
+        if next.val == 'S' {
+            return Some(vertices);
         }
-        let last_item = path.last().unwrap();
-        for neighbour in last_item.get_neighbour_coords() {
-            let next_cell = map.get(&(neighbour.0, neighbour.1));
-
-            if next_cell.is_some() && next_cell.unwrap().val == 'S' {
-                path.push(*next_cell.unwrap());
-                break;
-            }
-
-            if next_cell.is_some()
-                && !path.contains(&next_cell.unwrap())
-                && next_cell.unwrap().val != '.'
-            {
-                path.push(*next_cell.unwrap());
-            }
+        if !next.directions.contains(&heading.opposite()) {
+            return None;
         }
+        vertices.push((next.x, next.y));
+        heading = *next.directions.iter().find(|d| **d != heading.opposite())?;
     }
-    let loop_cells = path.iter().collect::<HashSet<_>>();
-    // println!("{:?}", loop_cells);
-    let mut total_count = 0;
-
-    for j in 0..height {
-        for i in 0..width {
-            let curr = map.get(&(i as i32, j as i32));
-            let mut winding_rule_count = 0;
-
-            if curr.is_none() || loop_cells.contains(&curr.unwrap()) {
-                continue;
-            }
+}
 
-            for k in i..width {
-                let next = map.get(&(k as i32, j as i32));
-                if next.is_none() {
-                    continue;
-                }
+// Twice the area enclosed by the loop through the centres of its cells
+fn shoelace_area2(vertices: &[(i32, i32)]) -> i64 {
+    let n = vertices.len();
+    (0..n)
+        .map(|i| {
+            let (x1, y1) = vertices[i];
+            let (x2, y2) = vertices[(i + 1) % n];
+            x1 as i64 * y2 as i64 - x2 as i64 * y1 as i64
+        })
+        .sum::<i64>()
+        .abs()
+}
 
-                if loop_cells.contains(&next.unwrap())
-                    && (next.unwrap().directions.contains(&Direction::Up))
-                {
-                    winding_rule_count += 1;
-                }
-            }
+fn main() {
+    let input: &str = include_str!("./input.txt");
+    let result: String = part2(input);
+    println!("Result: {}", result);
+}
 
-            if winding_rule_count % 2 == 1 {
-                total_count += 1;
-            }
-        }
-    }
-    total_count.to_string()
+fn part2(input: &str) -> String {
+    let (map, starting_cell) = parse(input);
+    let vertices = trace_loop(&map, &starting_cell);
+    // Pick's theorem: A = I + B/2 - 1 where the B boundary points are the loop cells
+    let interior = (shoelace_area2(&vertices) - vertices.len() as i64) / 2 + 1;
+    interior.to_string()
 }
 
 fn parse(input: &str) -> (HashMap<(i32, i32), Cell>, Cell) {
@@ -278,4 +241,16 @@ L7JLJL-JLJLJL--JLJ.L";
         let result: String = part2(test_input);
         assert_eq!(result, "10".to_string());
     }
+
+    #[test]
+    fn dead_end_into_start() {
+        // The pipe above S points into it but is not part of the loop
+        let test_input: &str = ".|...
+.S-7.
+.|.|.
+.L-J.
+.....";
+        let result: String = part2(test_input);
+        assert_eq!(result, "1".to_string());
+    }
 }
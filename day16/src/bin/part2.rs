@@ -1,5 +1,5 @@
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Clone, Debug, Copy)]
 struct Tile {
@@ -381,9 +381,9 @@ fn string_repr(tiles: &Vec<Tile>) -> String {
         .join("\n")
 }
 
-fn parse(input: &str) -> (Vec<Vec<Tile>>, HashMap<(i32, i32), Tile>) {
+fn parse(input: &str) -> (Vec<Vec<Tile>>, BTreeMap<(i32, i32), Tile>) {
     let mut cells = Vec::new();
-    let mut tiles = HashMap::new();
+    let mut tiles = BTreeMap::new();
     input.lines().enumerate().for_each(|(y, line)| {
         let mut row = Vec::new();
         line.chars().enumerate().for_each(|(x, c)| {
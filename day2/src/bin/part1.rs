@@ -1,60 +1,124 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::{alpha1, space1, u32},
+    combinator::all_consuming,
+    multi::separated_list1,
+    sequence::{delimited, separated_pair},
+    IResult,
+};
+use std::collections::{BTreeMap, HashMap};
+
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part1(input);
+    // Optionally pass a different bag, e.g. --bag=red=12,green=13,blue=14
+    let result: String =
+        match std::env::args().find_map(|a| a.strip_prefix("--bag=").map(String::from)) {
+            Some(bag) => {
+                let bag = parse_bag(&bag).expect("Should parse bag");
+                let games = parse_games(input).expect("Should parse games");
+                let ids = possible_games(&games, &bag);
+                println!("Possible games with {}: {:?}", format_bag(&bag), ids);
+                ids.iter().sum::<u32>().to_string()
+            }
+            None => part1(input),
+        };
     println!("Result: {}", result);
 }
 
-fn is_possible(red: u32, green: u32, blue: u32) -> bool {
-    return red <= 12 && green <= 13 && blue <= 14;
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Colour(String);
+
+// Number of cubes of each colour in the bag. Colours that are not listed have none
+type Bag = BTreeMap<Colour, u32>;
+
+impl Colour {
+    fn new(name: &str) -> Colour {
+        Colour(name.to_string())
+    }
 }
 
-fn parse_game(game_string: &str) -> bool {
-    let game = game_string.split(": ").last().unwrap();
-    let stages = game.split("; ");
-    let out = stages
-        .map(|stage| {
-            let mut red_count = 0;
-            let mut green_count = 0;
-            let mut blue_count = 0;
-
-            for count in stage.split(',').map(|x| x.trim()) {
-                if count.ends_with("red") {
-                    if let Ok(num) = count.trim_matches(|c: char| !c.is_digit(10)).parse::<u32>() {
-                        red_count += num;
-                    }
-                } else if count.ends_with("green") {
-                    if let Ok(num) = count.trim_matches(|c: char| !c.is_digit(10)).parse::<u32>() {
-                        green_count += num;
-                    }
-                } else if count.ends_with("blue") {
-                    if let Ok(num) = count.trim_matches(|c: char| !c.is_digit(10)).parse::<u32>() {
-                        blue_count += num;
-                    }
-                }
-            }
-            return is_possible(red_count, green_count, blue_count);
+#[derive(Debug)]
+struct Game {
+    id: u32,
+    rounds: Vec<HashMap<Colour, u32>>,
+}
+
+impl Game {
+    fn is_possible(&self, bag: &Bag) -> bool {
+        self.rounds.iter().all(|round| {
+            round
+                .iter()
+                .all(|(colour, count)| count <= bag.get(colour).unwrap_or(&0))
+        })
+    }
+}
+
+fn format_bag(bag: &Bag) -> String {
+    bag.iter()
+        .map(|(colour, count)| format!("{}={}", colour.0, count))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn parse_games(input: &str) -> Result<Vec<Game>, String> {
+    input
+        .trim()
+        .lines()
+        .map(|line| {
+            all_consuming(game)(line.trim())
+                .map(|(_, game)| game)
+                .map_err(|e| format!("Invalid game {:?}: {}", line.trim(), e))
         })
-        .collect::<Vec<bool>>()
+        .collect()
+}
+
+// Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+fn game(input: &str) -> IResult<&str, Game> {
+    let (input, id) = delimited(tag("Game "), u32, tag(": "))(input)?;
+    let (input, rounds) = separated_list1(tag("; "), round)(input)?;
+    Ok((input, Game { id, rounds }))
+}
+
+fn round(input: &str) -> IResult<&str, HashMap<Colour, u32>> {
+    let (input, cubes) = separated_list1(tag(", "), separated_pair(u32, space1, alpha1))(input)?;
+    let mut res = HashMap::new();
+    cubes.iter().for_each(|(count, colour)| {
+        *res.entry(Colour::new(colour)).or_insert(0) += count;
+    });
+    Ok((input, res))
+}
+
+// red=12,green=13,blue=14
+fn parse_bag(input: &str) -> Result<Bag, String> {
+    input
+        .split(',')
+        .map(|item| {
+            let (colour, count) = item
+                .trim()
+                .split_once('=')
+                .ok_or(format!("Invalid bag entry: {:?}", item))?;
+            let count = count
+                .trim()
+                .parse::<u32>()
+                .map_err(|e| format!("Invalid bag entry {:?}: {}", item, e))?;
+            Ok((Colour::new(colour.trim()), count))
+        })
+        .collect()
+}
+
+// IDs of the games that could have been played with this bag
+fn possible_games(games: &[Game], bag: &Bag) -> Vec<u32> {
+    games
         .iter()
-        .all(|&x| x);
-    return out;
+        .filter(|game| game.is_possible(bag))
+        .map(|game| game.id)
+        .collect()
 }
 
 fn part1(input: &str) -> String {
-    let games = input.trim().split("\n");
-    let result = games
-        .enumerate()
-        .map(|(i, game)| {
-            let is_possible = parse_game(game);
-            if is_possible {
-                return i + 1;
-            } else {
-                return 0;
-            }
-        })
-        .sum::<usize>()
-        .to_string();
-    return result;
+    let games = parse_games(input).expect("Should parse games");
+    let bag = parse_bag("red=12,green=13,blue=14").unwrap();
+    possible_games(&games, &bag).iter().sum::<u32>().to_string()
 }
 
 #[cfg(test)]
@@ -73,4 +137,21 @@ mod tests {
         let result: String = part1(test_input);
         assert_eq!(result, "8".to_string());
     }
+
+    #[test]
+    fn real_ids_and_open_colours() {
+        let test_input: &str = "Game 7: 3 blue, 4 red, 2 purple
+Game 42: 20 red; 1 blue
+Game 100: 1 green, 9 purple; 2 purple";
+        let games = parse_games(test_input).unwrap();
+        assert_eq!(games[2].rounds[0].get(&Colour::new("purple")), Some(&9));
+        // Nothing is possible without purple cubes in the bag
+        assert_eq!(part1(test_input), "0".to_string());
+        let bag = parse_bag("red=20,blue=3,purple=9,green=1").unwrap();
+        assert_eq!(possible_games(&games, &bag), vec![7, 42, 100]);
+        let bag = parse_bag("red=4,blue=3,purple=2").unwrap();
+        assert_eq!(possible_games(&games, &bag), vec![7]);
+        assert!(parse_games("Game 1: 3 blue; four red").is_err());
+        assert!(parse_games("Round 1: 3 blue").is_err());
+    }
 }
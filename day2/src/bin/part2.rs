@@ -1,49 +1,207 @@
+use nom::{
+    bytes::complete::tag,
+    character::complete::{alpha1, space1, u32},
+    combinator::all_consuming,
+    multi::separated_list1,
+    sequence::{delimited, separated_pair},
+    IResult,
+};
+use std::collections::{BTreeMap, HashMap};
+
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part2(input);
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    // Optionally show the minimal bag for one game, e.g. --minimal-bag=3
+    if let Some(id) = args.iter().find_map(|a| a.strip_prefix("--minimal-bag=")) {
+        let id = id.parse::<u32>().expect("Should parse game id");
+        let games = parse_games(input).expect("Should parse games");
+        let result = match games.iter().find(|game| game.id == id) {
+            Some(game) => {
+                let bag = game.minimal_bag();
+                format!("{} (power {})", format_bag(&bag), power(&bag))
+            }
+            None => format!("No game {}", id),
+        };
+        println!("Result: {}", result);
+        return;
+    }
+    // Optionally find the smallest bag that makes at least K games possible, e.g. --at-least=50
+    let result: String = match args.iter().find_map(|a| a.strip_prefix("--at-least=")) {
+        Some(k) => {
+            let k = k.parse::<usize>().expect("Should parse K");
+            let games = parse_games(input).expect("Should parse games");
+            match smallest_bag_for(&games, k) {
+                Some(bag) => {
+                    let ids = games
+                        .iter()
+                        .filter(|game| game.is_possible(&bag))
+                        .map(|game| game.id)
+                        .collect::<Vec<u32>>();
+                    println!("Possible games: {:?}", ids);
+                    format_bag(&bag)
+                }
+                None => format!("Only {} games", games.len()),
+            }
+        }
+        None => part2(input),
+    };
     println!("Result: {}", result);
 }
 
-fn parse_game(game_string: &str) -> u32 {
-    let game = game_string.split(": ").last().unwrap();
-    let stages = game.split("; ");
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Colour(String);
 
-    let mut biggest_red = 0;
-    let mut biggest_green = 0;
-    let mut biggest_blue = 0;
+// Number of cubes of each colour in the bag. Colours that are not listed have none
+type Bag = BTreeMap<Colour, u32>;
 
-    stages.for_each(|stage| {
-        let mut red_count = 0;
-        let mut green_count = 0;
-        let mut blue_count = 0;
+impl Colour {
+    fn new(name: &str) -> Colour {
+        Colour(name.to_string())
+    }
+}
 
-        for count in stage.split(',').map(|x| x.trim()) {
-            if count.ends_with("red") {
-                if let Ok(num) = count.trim_matches(|c: char| !c.is_digit(10)).parse::<u32>() {
-                    red_count += num;
-                }
-            } else if count.ends_with("green") {
-                if let Ok(num) = count.trim_matches(|c: char| !c.is_digit(10)).parse::<u32>() {
-                    green_count += num;
-                }
-            } else if count.ends_with("blue") {
-                if let Ok(num) = count.trim_matches(|c: char| !c.is_digit(10)).parse::<u32>() {
-                    blue_count += num;
-                }
-            }
-        }
+#[derive(Debug)]
+struct Game {
+    id: u32,
+    rounds: Vec<HashMap<Colour, u32>>,
+}
+
+impl Game {
+    fn is_possible(&self, bag: &Bag) -> bool {
+        self.rounds.iter().all(|round| {
+            round
+                .iter()
+                .all(|(colour, count)| count <= bag.get(colour).unwrap_or(&0))
+        })
+    }
+
+    // Fewest cubes of each colour that makes this game possible
+    fn minimal_bag(&self) -> Bag {
+        let mut bag = Bag::new();
+        self.rounds.iter().flatten().for_each(|(colour, count)| {
+            let curr = bag.entry(colour.clone()).or_insert(0);
+            *curr = (*curr).max(*count);
+        });
+        bag
+    }
+}
+
+fn format_bag(bag: &Bag) -> String {
+    bag.iter()
+        .map(|(colour, count)| format!("{}={}", colour.0, count))
+        .collect::<Vec<String>>()
+        .join(",")
+}
 
-        biggest_red = red_count.max(biggest_red);
-        biggest_green = green_count.max(biggest_green);
-        biggest_blue = blue_count.max(biggest_blue);
+fn parse_games(input: &str) -> Result<Vec<Game>, String> {
+    input
+        .trim()
+        .lines()
+        .map(|line| {
+            all_consuming(game)(line.trim())
+                .map(|(_, game)| game)
+                .map_err(|e| format!("Invalid game {:?}: {}", line.trim(), e))
+        })
+        .collect()
+}
+
+// Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+fn game(input: &str) -> IResult<&str, Game> {
+    let (input, id) = delimited(tag("Game "), u32, tag(": "))(input)?;
+    let (input, rounds) = separated_list1(tag("; "), round)(input)?;
+    Ok((input, Game { id, rounds }))
+}
+
+fn round(input: &str) -> IResult<&str, HashMap<Colour, u32>> {
+    let (input, cubes) = separated_list1(tag(", "), separated_pair(u32, space1, alpha1))(input)?;
+    let mut res = HashMap::new();
+    cubes.iter().for_each(|(count, colour)| {
+        *res.entry(Colour::new(colour)).or_insert(0) += count;
     });
-    return biggest_red * biggest_green * biggest_blue;
+    Ok((input, res))
+}
+
+fn power(bag: &Bag) -> u32 {
+    ["red", "green", "blue"]
+        .iter()
+        .map(|colour| *bag.get(&Colour::new(colour)).unwrap_or(&0))
+        .product()
+}
+
+// Bag with the fewest cubes in total that makes at least k games possible
+fn smallest_bag_for(games: &[Game], k: usize) -> Option<Bag> {
+    if k > games.len() {
+        return None;
+    }
+    if k == 0 {
+        return Some(Bag::new());
+    }
+    let minimal_bags = games
+        .iter()
+        .map(|game| game.minimal_bag())
+        .collect::<Vec<Bag>>();
+    let colours = minimal_bags
+        .iter()
+        .flat_map(|bag| bag.keys().cloned())
+        .collect::<std::collections::BTreeSet<Colour>>()
+        .into_iter()
+        .collect::<Vec<Colour>>();
+    let mut best = None;
+    search_bag(
+        &minimal_bags.iter().collect::<Vec<&Bag>>(),
+        &colours,
+        k,
+        Bag::new(),
+        &mut best,
+    );
+    best
+}
+
+// Fix the count of each colour in turn to one of the counts the remaining games need.
+// The last colour only has to cover the k-th smallest remaining game
+fn search_bag(games: &[&Bag], colours: &[Colour], k: usize, chosen: Bag, best: &mut Option<Bag>) {
+    let total = |bag: &Bag| bag.values().sum::<u32>();
+    if colours.is_empty() {
+        if games.len() >= k && best.as_ref().is_none_or(|b| total(&chosen) < total(b)) {
+            *best = Some(chosen);
+        }
+        return;
+    }
+    let colour = &colours[0];
+    let count = |bag: &Bag| *bag.get(colour).unwrap_or(&0);
+    let mut counts = games.iter().map(|bag| count(bag)).collect::<Vec<u32>>();
+    counts.sort();
+    if colours.len() == 1 {
+        let mut bag = chosen;
+        if k > 0 {
+            bag.insert(colour.clone(), counts[k - 1]);
+        }
+        search_bag(games, &[], k, bag, best);
+        return;
+    }
+    counts.dedup();
+    for c in counts {
+        let remaining = games
+            .iter()
+            .filter(|bag| count(bag) <= c)
+            .cloned()
+            .collect::<Vec<&Bag>>();
+        if remaining.len() < k {
+            continue;
+        }
+        let mut bag = chosen.clone();
+        bag.insert(colour.clone(), c);
+        search_bag(&remaining, &colours[1..], k, bag, best);
+    }
 }
 
 fn part2(input: &str) -> String {
-    let games = input.trim().split("\n");
-    let result = games.map(|game| parse_game(game)).sum::<u32>().to_string();
-    return result;
+    let games = parse_games(input).expect("Should parse games");
+    games
+        .iter()
+        .map(|game| power(&game.minimal_bag()))
+        .sum::<u32>()
+        .to_string()
 }
 
 #[cfg(test)]
@@ -62,4 +220,29 @@ mod tests {
         let result: String = part2(test_input);
         assert_eq!(result, "2286".to_string());
     }
+
+    #[test]
+    fn smallest_bag() {
+        let test_input: &str = "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
+        let games = parse_games(test_input).unwrap();
+        assert_eq!(format_bag(&games[0].minimal_bag()), "blue=6,green=2,red=4");
+        assert_eq!(
+            format_bag(&smallest_bag_for(&games, 1).unwrap()),
+            "blue=4,green=3,red=1"
+        );
+        assert_eq!(
+            format_bag(&smallest_bag_for(&games, 3).unwrap()),
+            "blue=6,green=3,red=6"
+        );
+        assert_eq!(
+            format_bag(&smallest_bag_for(&games, 5).unwrap()),
+            "blue=15,green=13,red=20"
+        );
+        assert!(smallest_bag_for(&games, 6).is_none());
+        assert_eq!(smallest_bag_for(&games, 0), Some(Bag::new()));
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, VecDeque};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Pulse {
@@ -35,7 +35,7 @@ impl FlipFlop {
 struct Conjunction {
     state: bool, // is it all high?
     destinations: Vec<String>,
-    past_inputs: HashMap<String, Pulse>,
+    past_inputs: BTreeMap<String, Pulse>,
 }
 
 impl Conjunction {
@@ -163,8 +163,8 @@ fn part1(input: &str) -> String {
     (high_count * low_count).to_string()
 }
 
-fn parse(input: &str) -> (HashMap<String, Module>, Vec<String>) {
-    let mut modules = HashMap::new();
+fn parse(input: &str) -> (BTreeMap<String, Module>, Vec<String>) {
+    let mut modules = BTreeMap::new();
     let mut broadcaster = Vec::new();
     let mut tmp = Vec::new();
     input.lines().for_each(|line| {
@@ -184,7 +184,7 @@ fn parse(input: &str) -> (HashMap<String, Module>, Vec<String>) {
             let new_mod = Module::Conjunction(Conjunction {
                 state: false,
                 destinations: dests,
-                past_inputs: HashMap::new(),
+                past_inputs: BTreeMap::new(),
             });
             tmp.push((curr[1..].to_string(), new_mod));
         } else {
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, VecDeque};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum Pulse {
@@ -35,7 +35,7 @@ impl FlipFlop {
 struct Conjunction {
     state: bool, // is it all high?
     destinations: Vec<String>,
-    past_inputs: HashMap<String, Pulse>,
+    past_inputs: BTreeMap<String, Pulse>,
 }
 
 impl Conjunction {
@@ -115,7 +115,7 @@ fn part2(input: &str) -> String {
     // Hard coded
     // let direct_child = "bn"; // has to be low
     let indirect_childs = vec!["pl", "mz", "lz", "zm"]; // has to be high
-    let mut periods: HashMap<String, Vec<i64>> = HashMap::new();
+    let mut periods: BTreeMap<String, Vec<i64>> = BTreeMap::new();
     for i in 0..20_000 {
         // single button push
         broadcaster.iter().for_each(|d| {
@@ -189,8 +189,8 @@ fn part2(input: &str) -> String {
         .to_string()
 }
 
-fn parse(input: &str) -> (HashMap<String, Module>, Vec<String>) {
-    let mut modules = HashMap::new();
+fn parse(input: &str) -> (BTreeMap<String, Module>, Vec<String>) {
+    let mut modules = BTreeMap::new();
     let mut broadcaster = Vec::new();
     let mut tmp = Vec::new();
     input.lines().for_each(|line| {
@@ -210,7 +210,7 @@ fn parse(input: &str) -> (HashMap<String, Module>, Vec<String>) {
             let new_mod = Module::Conjunction(Conjunction {
                 state: false,
                 destinations: dests,
-                past_inputs: HashMap::new(),
+                past_inputs: BTreeMap::new(),
             });
             tmp.push((curr[1..].to_string(), new_mod));
         } else {
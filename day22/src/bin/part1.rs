@@ -1,5 +1,5 @@
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 struct Brick {
@@ -40,7 +40,7 @@ fn part1(input: &str) -> String {
     let mut bricks = parse(input);
     bricks.sort();
 
-    let mut mapping: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new(); // This mapping represents the active z coordinates and tte brick index at a given x,y coordinate
+    let mut mapping: BTreeMap<(i32, i32), Vec<(i32, i32)>> = BTreeMap::new(); // This mapping represents the active z coordinates and tte brick index at a given x,y coordinate
     bricks.iter().enumerate().for_each(|(i, x)| {
         let first_xy = (x.first_end.0, x.first_end.1);
         let second_xy = (x.second_end.0, x.second_end.1);
@@ -80,7 +80,7 @@ fn part1(input: &str) -> String {
             }
         }
     });
-    let mut edges = HashSet::new(); // (Source, Destination)
+    let mut edges = BTreeSet::new(); // (Source, Destination)
     mapping.iter().for_each(|(_k, v)| {
         let idxs = v.iter().map(|(idx, _val)| idx).collect::<Vec<_>>();
         let vals = v.iter().map(|(_idx, val)| val).collect::<Vec<_>>();
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Eq, PartialEq, Debug)]
 enum Tile {
@@ -61,8 +61,8 @@ fn part1(input: &str) -> String {
     paths.iter().map(|x| x.len() - 1).max().unwrap().to_string()
 }
 
-fn parse(input: &str) -> (HashMap<(i32, i32), Tile>, (i32, i32), (i32, i32)) {
-    let mut tiles = HashMap::new();
+fn parse(input: &str) -> (BTreeMap<(i32, i32), Tile>, (i32, i32), (i32, i32)) {
+    let mut tiles = BTreeMap::new();
     let mut start = (-1, -1);
     let mut goal = (-1, -1);
 
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, VecDeque};
 
 #[derive(Eq, PartialEq, Debug)]
 enum Tile {
@@ -48,10 +48,10 @@ fn part2(input: &str) -> String {
 }
 
 fn get_intersections(
-    tiles: HashMap<(i32, i32), Tile>,
+    tiles: BTreeMap<(i32, i32), Tile>,
     start: (i32, i32),
     end: (i32, i32),
-) -> HashMap<(i32, i32), Intersection> {
+) -> BTreeMap<(i32, i32), Intersection> {
     let mut intersections = Vec::new();
     for (pos, tile) in tiles.iter() {
         if *tile == Tile::Path {
@@ -71,10 +71,10 @@ fn get_intersections(
     intersections.push(start);
     intersections.push(end);
     // for each intersection, do a bfs to find adjacent intersections
-    let mut res = HashMap::new();
+    let mut res = BTreeMap::new();
     intersections.iter().for_each(|pos| {
         let mut adj_list = Vec::new();
-        let mut visited = HashMap::new();
+        let mut visited = BTreeMap::new();
         let mut queue = VecDeque::new();
         queue.push_back((*pos, 0));
 
@@ -103,8 +103,8 @@ fn get_intersections(
     res
 }
 
-fn parse(input: &str) -> (HashMap<(i32, i32), Tile>, (i32, i32), (i32, i32)) {
-    let mut tiles = HashMap::new();
+fn parse(input: &str) -> (BTreeMap<(i32, i32), Tile>, (i32, i32), (i32, i32)) {
+    let mut tiles = BTreeMap::new();
     let mut start = (-1, -1);
     let mut goal = (-1, -1);
 
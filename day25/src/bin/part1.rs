@@ -1,5 +1,5 @@
 use itertools::Itertools;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashSet, VecDeque};
 
 fn main() {
     let input: &str = include_str!("input.txt");
@@ -9,12 +9,12 @@ fn main() {
 
 fn part1(input: &str) -> String {
     let (edges, graph, all_nodes) = parse(input);
-    let mut paths = HashMap::new();
+    let mut paths = BTreeMap::new();
     all_nodes.iter().for_each(|node| {
         let path = bfs(graph.clone(), node.clone());
         paths.insert(node.clone(), path);
     });
-    let mut edge_counts = HashMap::new();
+    let mut edge_counts = BTreeMap::new();
 
     all_nodes.iter().combinations(2).for_each(|x| {
         let first = x[0].clone();
@@ -37,6 +37,7 @@ fn part1(input: &str) -> String {
         })
     });
     let mut flattend = edge_counts.iter().collect::<Vec<_>>();
+    // BTreeMap iterates in edge order and sort_by is stable so ties are always broken the same way
     flattend.sort_by(|a, b| (a.1.cmp(b.1)).reverse()); // Sort by descending order
     let top3 = flattend.iter().take(3).collect::<Vec<_>>();
     let mut new_edges = edges.clone();
@@ -70,12 +71,12 @@ fn part1(input: &str) -> String {
     (visited * (total - visited)).to_string()
 }
 
-fn bfs(graph: HashMap<String, Vec<String>>, start: String) -> HashMap<String, Vec<String>> {
+fn bfs(graph: BTreeMap<String, Vec<String>>, start: String) -> BTreeMap<String, Vec<String>> {
     let mut queue = VecDeque::new();
     let mut visited = HashSet::new();
     queue.push_back(vec![start]);
 
-    let mut paths = HashMap::new();
+    let mut paths = BTreeMap::new();
 
     while let Some(curr_path) = queue.pop_front() {
         let curr_node = curr_path.last().unwrap().clone();
@@ -99,7 +100,7 @@ fn parse(
     input: &str,
 ) -> (
     Vec<(String, String)>,
-    HashMap<String, Vec<String>>,
+    BTreeMap<String, Vec<String>>,
     Vec<String>,
 ) {
     let mut edges = Vec::new();
@@ -121,8 +122,8 @@ fn parse(
     (edges, graph, all_nodes)
 }
 
-fn edge_to_graph(edges: Vec<(String, String)>) -> (HashMap<String, Vec<String>>, Vec<String>) {
-    let mut graph = HashMap::new();
+fn edge_to_graph(edges: Vec<(String, String)>) -> (BTreeMap<String, Vec<String>>, Vec<String>) {
+    let mut graph = BTreeMap::new();
     let all_nodes = edges
         .iter()
         .flat_map(|(x, y)| vec![x.clone(), y.clone()])
@@ -1,75 +1,231 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Number {
+    row: i32,
+    col_start: i32,
+    col_end: i32, // inclusive
+    value: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Symbol {
+    row: i32,
+    col: i32,
+    kind: char,
+}
+
 #[derive(Debug)]
-struct Cell {
-    x: i32,
-    y: i32,
-    value: char,
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    number_at: HashMap<(i32, i32), usize>, // (row, col) of every digit -> index into numbers
+    symbol_at: HashMap<(i32, i32), usize>, // (row, col) -> index into symbols
 }
 
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part1(input);
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let result: String = run_query(input, &args).unwrap_or_else(|| part1(input));
     println!("Result: {}", result);
 }
 
-fn part1(input: &str) -> String {
-    let matrix = string_to_matrix(input).unwrap();
-    let num_rows = matrix.len() as i32;
-    let num_cols = matrix[0].len() as i32;
-    let mut vals = Vec::new();
-
-    matrix.iter().for_each(|row| {
-        let mut curr_num = 0;
-        let mut is_adjacent = false; // adjacent to a symbol
-
-        row.iter().enumerate().for_each(|(x, cell)| {
-            let value = cell.value;
-            if value.is_digit(10) {
-                let start_x = (cell.x - 1).max(0);
-                let end_x = (cell.x + 1).min(num_cols - 1);
-                let start_y = (cell.y - 1).max(0);
-                let end_y = (cell.y + 1).min(num_rows - 1);
-                for x in [start_x, cell.x, end_x].iter() {
-                    // note that x and y are not numbers, but references to numbers (indices)
-                    for y in [start_y, cell.y, end_y].iter() {
-                        if *x == cell.x && *y == cell.y {
-                            continue;
-                        }
-                        let neighbour = &matrix[*y as usize][*x as usize]; // need to borrow because matrix is not owned by this scope
-                        if !(neighbour.value.is_digit(10)) && (neighbour.value != '.') {
-                            is_adjacent = true;
-                        }
+impl Schematic {
+    fn parse(input: &str) -> Schematic {
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        let mut number_at = HashMap::new();
+        let mut symbol_at = HashMap::new();
+
+        input.lines().enumerate().for_each(|(row, line)| {
+            let row = row as i32;
+            let mut curr: Option<Number> = None;
+            for (col, value) in line.chars().enumerate() {
+                let col = col as i32;
+                if let Some(digit) = value.to_digit(10) {
+                    let number = curr.get_or_insert(Number {
+                        row,
+                        col_start: col,
+                        col_end: col,
+                        value: 0,
+                    });
+                    number.col_end = col;
+                    number.value = number.value * 10 + digit;
+                    number_at.insert((row, col), numbers.len());
+                    continue;
+                }
+                if let Some(number) = curr.take() {
+                    numbers.push(number);
+                }
+                if value != '.' {
+                    symbol_at.insert((row, col), symbols.len());
+                    symbols.push(Symbol {
+                        row,
+                        col,
+                        kind: value,
+                    });
+                }
+            }
+            if let Some(number) = curr.take() {
+                numbers.push(number);
+            }
+        });
+
+        Schematic {
+            numbers,
+            symbols,
+            number_at,
+            symbol_at,
+        }
+    }
+
+    fn numbers_adjacent_to(&self, symbol: &Symbol) -> Vec<&Number> {
+        let mut idxs = Vec::new();
+        for row in symbol.row - 1..=symbol.row + 1 {
+            for col in symbol.col - 1..=symbol.col + 1 {
+                if let Some(&idx) = self.number_at.get(&(row, col)) {
+                    if !idxs.contains(&idx) {
+                        idxs.push(idx);
                     }
                 }
-                curr_num = curr_num * 10 + value.to_digit(10).unwrap();
             }
-            if (x == row.len() - 1) || !(value.is_digit(10)) {
-                if is_adjacent {
-                    vals.push(curr_num);
+        }
+        idxs.iter().map(|&idx| &self.numbers[idx]).collect()
+    }
+
+    fn symbols_adjacent_to(&self, number: &Number) -> Vec<&Symbol> {
+        let mut res = Vec::new();
+        for row in number.row - 1..=number.row + 1 {
+            for col in number.col_start - 1..=number.col_end + 1 {
+                if let Some(&idx) = self.symbol_at.get(&(row, col)) {
+                    res.push(&self.symbols[idx]);
                 }
-                is_adjacent = false;
-                curr_num = 0;
             }
-        });
-    });
-    // println!("{:?}", vals);
-    return vals.iter().sum::<u32>().to_string();
+        }
+        res
+    }
 }
 
-fn string_to_matrix(input: &str) -> Result<Vec<Vec<Cell>>, &'static str> {
-    let mut matrix: Vec<Vec<Cell>> = Vec::new();
-    input.lines().enumerate().for_each(|(y, line)| {
-        matrix.push(Vec::new());
-        line.chars().enumerate().for_each(|(x, value)| {
-            let cell = Cell {
-                x: x as i32,
-                y: y as i32,
-                value: value,
-            };
-            matrix[y].push(cell);
-        });
-    });
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Count {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aggregate {
+    Sum,
+    Product,
+    Max,
+    Count,
+}
 
-    Ok(matrix)
+// For each symbol of a kind (None for any symbol) whose number of adjacent numbers matches count,
+// combine the adjacent numbers with aggregate
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SymbolQuery {
+    kind: Option<char>,
+    count: Count,
+    aggregate: Aggregate,
+}
+
+impl SymbolQuery {
+    // <kind|any>:<exactly|at-least>:<k>:<sum|product|max|count> e.g. *:exactly:2:product
+    fn parse(input: &str) -> Result<SymbolQuery, String> {
+        let parts = input.split(':').collect::<Vec<&str>>();
+        if parts.len() != 4 {
+            return Err(format!("Invalid query: {}", input));
+        }
+        let kind = match parts[0] {
+            "any" => None,
+            k if k.chars().count() == 1 => k.chars().next(),
+            k => return Err(format!("Invalid symbol kind: {}", k)),
+        };
+        let k = parts[2]
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid count {}: {}", parts[2], e))?;
+        let count = match parts[1] {
+            "exactly" => Count::Exactly(k),
+            "at-least" => Count::AtLeast(k),
+            c => return Err(format!("Invalid count mode: {}", c)),
+        };
+        let aggregate = match parts[3] {
+            "sum" => Aggregate::Sum,
+            "product" => Aggregate::Product,
+            "max" => Aggregate::Max,
+            "count" => Aggregate::Count,
+            a => return Err(format!("Invalid aggregate: {}", a)),
+        };
+        Ok(SymbolQuery {
+            kind,
+            count,
+            aggregate,
+        })
+    }
+}
+
+impl Schematic {
+    fn query<'a>(&'a self, query: &SymbolQuery) -> Vec<(&'a Symbol, u64)> {
+        self.symbols
+            .iter()
+            .filter(|symbol| query.kind.is_none_or(|kind| symbol.kind == kind))
+            .filter_map(|symbol| {
+                let numbers = self.numbers_adjacent_to(symbol);
+                let matches = match query.count {
+                    Count::Exactly(k) => numbers.len() == k,
+                    Count::AtLeast(k) => numbers.len() >= k,
+                };
+                if !matches {
+                    return None;
+                }
+                let values = numbers.iter().map(|number| number.value as u64);
+                let res = match query.aggregate {
+                    Aggregate::Sum => values.sum(),
+                    Aggregate::Product => values.product(),
+                    Aggregate::Max => values.max().unwrap_or(0),
+                    Aggregate::Count => numbers.len() as u64,
+                };
+                Some((symbol, res))
+            })
+            .collect()
+    }
+
+    // Numbers that do not touch any symbol
+    fn isolated_numbers(&self) -> Vec<&Number> {
+        self.numbers
+            .iter()
+            .filter(|number| self.symbols_adjacent_to(number).is_empty())
+            .collect()
+    }
+}
+
+// Answer a variant question from the command line instead of the puzzle itself
+fn run_query(input: &str, args: &[String]) -> Option<String> {
+    let schematic = Schematic::parse(input);
+    if args.iter().any(|a| a == "--isolated") {
+        let values = schematic
+            .isolated_numbers()
+            .iter()
+            .map(|number| number.value)
+            .collect::<Vec<u32>>();
+        println!("Isolated numbers: {:?}", values);
+        return Some(values.iter().sum::<u32>().to_string());
+    }
+    let query = args.iter().find_map(|a| a.strip_prefix("--query="))?;
+    let query = SymbolQuery::parse(query).expect("Should parse query");
+    let res = schematic.query(&query);
+    Some(res.iter().map(|(_, v)| v).sum::<u64>().to_string())
+}
+
+fn part1(input: &str) -> String {
+    let schematic = Schematic::parse(input);
+    let total = schematic.numbers.iter().map(|n| n.value).sum::<u32>();
+    let isolated = schematic
+        .isolated_numbers()
+        .iter()
+        .map(|n| n.value)
+        .sum::<u32>();
+    (total - isolated).to_string()
 }
 
 #[cfg(test)]
@@ -91,4 +247,24 @@ mod tests {
         let result: String = part1(test_input);
         assert_eq!(result, "4361".to_string());
     }
+
+    #[test]
+    fn adjacency_index() {
+        let schematic = Schematic::parse("467..114..\n...*......\n..35..633.");
+        assert_eq!(schematic.numbers.len(), 4);
+        let star = &schematic.symbols[0];
+        let values = schematic
+            .numbers_adjacent_to(star)
+            .iter()
+            .map(|number| number.value)
+            .collect::<Vec<u32>>();
+        assert_eq!(values, vec![467, 35]);
+        assert!(schematic
+            .symbols_adjacent_to(&schematic.numbers[1])
+            .is_empty());
+        assert_eq!(
+            schematic.symbols_adjacent_to(&schematic.numbers[2]),
+            vec![star]
+        );
+    }
 }
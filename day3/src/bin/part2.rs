@@ -1,138 +1,235 @@
-#[derive(Debug, Eq, PartialEq, Copy, Clone)]
-struct Cell {
-    x: i32,
-    y: i32,
-    value: char,
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Number {
+    row: i32,
+    col_start: i32,
+    col_end: i32, // inclusive
+    value: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Symbol {
+    row: i32,
+    col: i32,
+    kind: char,
+}
+
+#[derive(Debug)]
+struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    number_at: HashMap<(i32, i32), usize>, // (row, col) of every digit -> index into numbers
+    symbol_at: HashMap<(i32, i32), usize>, // (row, col) -> index into symbols
 }
 
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part2(input);
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let result: String = run_query(input, &args).unwrap_or_else(|| part2(input));
     println!("Result: {}", result);
 }
 
-fn part2(input: &str) -> String {
-    let matrix = string_to_matrix(input).unwrap();
-    let num_rows = matrix.len() as i32;
-    let num_cols = matrix[0].len() as i32;
-    let mut score = 0;
-
-    matrix.iter().enumerate().for_each(|(y, row)| {
-        row.iter().enumerate().for_each(|(x, cell)| {
-            let value = cell.value;
-            if value == '*' {
-                let numerical_neighbours = dfs(&matrix, x as i32, y as i32, num_rows, num_cols);
-                let gear_ratio = gear_ratio(numerical_neighbours);
-                score += gear_ratio;
+impl Schematic {
+    fn parse(input: &str) -> Schematic {
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        let mut number_at = HashMap::new();
+        let mut symbol_at = HashMap::new();
+
+        input.lines().enumerate().for_each(|(row, line)| {
+            let row = row as i32;
+            let mut curr: Option<Number> = None;
+            for (col, value) in line.chars().enumerate() {
+                let col = col as i32;
+                if let Some(digit) = value.to_digit(10) {
+                    let number = curr.get_or_insert(Number {
+                        row,
+                        col_start: col,
+                        col_end: col,
+                        value: 0,
+                    });
+                    number.col_end = col;
+                    number.value = number.value * 10 + digit;
+                    number_at.insert((row, col), numbers.len());
+                    continue;
+                }
+                if let Some(number) = curr.take() {
+                    numbers.push(number);
+                }
+                if value != '.' {
+                    symbol_at.insert((row, col), symbols.len());
+                    symbols.push(Symbol {
+                        row,
+                        col,
+                        kind: value,
+                    });
+                }
+            }
+            if let Some(number) = curr.take() {
+                numbers.push(number);
             }
         });
-    });
-    // println!("{:?}", vals);
-    return score.to_string();
-}
 
-fn gear_ratio(cells: Vec<Cell>) -> i32 {
-    let mut sorted_cells = cells.clone();
-    sorted_cells.sort_by(|a, b| {
-        // Sort by 'y' field first
-        let y_comparison = a.y.cmp(&b.y);
-        if y_comparison == std::cmp::Ordering::Equal {
-            // If 'y' values are equal, sort by 'x' field
-            a.x.cmp(&b.x)
-        } else {
-            y_comparison
+        Schematic {
+            numbers,
+            symbols,
+            number_at,
+            symbol_at,
         }
-    });
+    }
 
-    let mut res = Vec::new();
-    println!("{:?}", sorted_cells);
-    let mut unique_y = sorted_cells
-        .iter()
-        .map(|cell| cell.y)
-        .collect::<std::collections::HashSet<_>>()
-        .into_iter()
-        .collect::<Vec<_>>();
-    unique_y.sort();
-    for y in unique_y.iter() {
-        let mut prev = sorted_cells
-            .iter()
-            .filter(|cell| cell.y == *y)
-            .next()
-            .unwrap()
-            .x;
-        let mut curr = 0;
-        for cell in sorted_cells.iter() {
-            if cell.y == *y {
-                if (cell.x - prev <= 1) && (cell.x - prev >= 0) {
-                    curr = curr * 10 + cell.value.to_digit(10).unwrap();
-                } else {
-                    // reset
-                    res.push(curr as i32);
-                    curr = cell.value.to_digit(10).unwrap();
+    fn numbers_adjacent_to(&self, symbol: &Symbol) -> Vec<&Number> {
+        let mut idxs = Vec::new();
+        for row in symbol.row - 1..=symbol.row + 1 {
+            for col in symbol.col - 1..=symbol.col + 1 {
+                if let Some(&idx) = self.number_at.get(&(row, col)) {
+                    if !idxs.contains(&idx) {
+                        idxs.push(idx);
+                    }
                 }
-                prev = cell.x;
             }
         }
-        if curr != 0 {
-            res.push(curr as i32);
-        }
+        idxs.iter().map(|&idx| &self.numbers[idx]).collect()
     }
-    if res.len() != 2 {
-        return 0;
-    } else {
-        return res[0] * res[1];
+
+    fn symbols_adjacent_to(&self, number: &Number) -> Vec<&Symbol> {
+        let mut res = Vec::new();
+        for row in number.row - 1..=number.row + 1 {
+            for col in number.col_start - 1..=number.col_end + 1 {
+                if let Some(&idx) = self.symbol_at.get(&(row, col)) {
+                    res.push(&self.symbols[idx]);
+                }
+            }
+        }
+        res
     }
 }
 
-fn dfs(matrix: &Vec<Vec<Cell>>, x: i32, y: i32, num_rows: i32, num_cols: i32) -> Vec<Cell> {
-    let mut stack = Vec::new();
-    let mut visited = Vec::new();
-    let mut res = Vec::new();
-    stack.push(matrix[y as usize][x as usize]);
-    while !stack.is_empty() {
-        let cell = stack.pop().unwrap();
-        visited.push(cell);
-        if cell.value.is_digit(10) && !(res.contains(&cell)) {
-            res.push(cell);
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Count {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Aggregate {
+    Sum,
+    Product,
+    Max,
+    Count,
+}
+
+// For each symbol of a kind (None for any symbol) whose number of adjacent numbers matches count,
+// combine the adjacent numbers with aggregate
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SymbolQuery {
+    kind: Option<char>,
+    count: Count,
+    aggregate: Aggregate,
+}
+
+impl SymbolQuery {
+    // <kind|any>:<exactly|at-least>:<k>:<sum|product|max|count> e.g. *:exactly:2:product
+    fn parse(input: &str) -> Result<SymbolQuery, String> {
+        let parts = input.split(':').collect::<Vec<&str>>();
+        if parts.len() != 4 {
+            return Err(format!("Invalid query: {}", input));
         }
-        let start_x = (cell.x - 1).max(0);
-        let end_x = (cell.x + 1).min(num_cols - 1);
-        let start_y = (cell.y - 1).max(0);
-        let end_y = (cell.y + 1).min(num_rows - 1);
-
-        for x in [start_x, cell.x, end_x].iter() {
-            // note that x and y are not numbers, but references to numbers (indices)
-            for y in [start_y, cell.y, end_y].iter() {
-                if *x == cell.x && *y == cell.y {
-                    continue;
-                }
-                let neighbour = &matrix[*y as usize][*x as usize]; // need to borrow because matrix is not owned by this scop
+        let kind = match parts[0] {
+            "any" => None,
+            k if k.chars().count() == 1 => k.chars().next(),
+            k => return Err(format!("Invalid symbol kind: {}", k)),
+        };
+        let k = parts[2]
+            .parse::<usize>()
+            .map_err(|e| format!("Invalid count {}: {}", parts[2], e))?;
+        let count = match parts[1] {
+            "exactly" => Count::Exactly(k),
+            "at-least" => Count::AtLeast(k),
+            c => return Err(format!("Invalid count mode: {}", c)),
+        };
+        let aggregate = match parts[3] {
+            "sum" => Aggregate::Sum,
+            "product" => Aggregate::Product,
+            "max" => Aggregate::Max,
+            "count" => Aggregate::Count,
+            a => return Err(format!("Invalid aggregate: {}", a)),
+        };
+        Ok(SymbolQuery {
+            kind,
+            count,
+            aggregate,
+        })
+    }
+}
 
-                if !(visited.contains(neighbour)) && (neighbour.value.is_digit(10)) {
-                    stack.push(*neighbour);
+impl Schematic {
+    fn query<'a>(&'a self, query: &SymbolQuery) -> Vec<(&'a Symbol, u64)> {
+        self.symbols
+            .iter()
+            .filter(|symbol| query.kind.is_none_or(|kind| symbol.kind == kind))
+            .filter_map(|symbol| {
+                let numbers = self.numbers_adjacent_to(symbol);
+                let matches = match query.count {
+                    Count::Exactly(k) => numbers.len() == k,
+                    Count::AtLeast(k) => numbers.len() >= k,
+                };
+                if !matches {
+                    return None;
                 }
-            }
-        }
+                let values = numbers.iter().map(|number| number.value as u64);
+                let res = match query.aggregate {
+                    Aggregate::Sum => values.sum(),
+                    Aggregate::Product => values.product(),
+                    Aggregate::Max => values.max().unwrap_or(0),
+                    Aggregate::Count => numbers.len() as u64,
+                };
+                Some((symbol, res))
+            })
+            .collect()
     }
 
-    return res;
+    // Numbers that do not touch any symbol
+    fn isolated_numbers(&self) -> Vec<&Number> {
+        self.numbers
+            .iter()
+            .filter(|number| self.symbols_adjacent_to(number).is_empty())
+            .collect()
+    }
 }
 
-fn string_to_matrix(input: &str) -> Result<Vec<Vec<Cell>>, &'static str> {
-    let mut matrix: Vec<Vec<Cell>> = Vec::new();
-    input.lines().enumerate().for_each(|(y, line)| {
-        matrix.push(Vec::new());
-        line.chars().enumerate().for_each(|(x, value)| {
-            let cell = Cell {
-                x: x as i32,
-                y: y as i32,
-                value: value,
-            };
-            matrix[y].push(cell);
-        });
-    });
+// Answer a variant question from the command line instead of the puzzle itself
+fn run_query(input: &str, args: &[String]) -> Option<String> {
+    let schematic = Schematic::parse(input);
+    if args.iter().any(|a| a == "--isolated") {
+        let values = schematic
+            .isolated_numbers()
+            .iter()
+            .map(|number| number.value)
+            .collect::<Vec<u32>>();
+        println!("Isolated numbers: {:?}", values);
+        return Some(values.iter().sum::<u32>().to_string());
+    }
+    let query = args.iter().find_map(|a| a.strip_prefix("--query="))?;
+    let query = SymbolQuery::parse(query).expect("Should parse query");
+    let res = schematic.query(&query);
+    Some(res.iter().map(|(_, v)| v).sum::<u64>().to_string())
+}
 
-    Ok(matrix)
+fn part2(input: &str) -> String {
+    let schematic = Schematic::parse(input);
+    let gears = SymbolQuery {
+        kind: Some('*'),
+        count: Count::Exactly(2),
+        aggregate: Aggregate::Product,
+    };
+    schematic
+        .query(&gears)
+        .iter()
+        .map(|(_, ratio)| ratio)
+        .sum::<u64>()
+        .to_string()
 }
 
 #[cfg(test)]
@@ -154,4 +251,41 @@ mod tests {
         let result: String = part2(test_input);
         assert_eq!(result, "467835".to_string());
     }
+
+    #[test]
+    fn queries() {
+        let test_input: &str = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+        let schematic = Schematic::parse(test_input);
+        let query = SymbolQuery::parse("*:at-least:1:count").unwrap();
+        assert_eq!(schematic.query(&query).len(), 3);
+        let query = SymbolQuery::parse("any:exactly:1:max").unwrap();
+        let res = schematic
+            .query(&query)
+            .iter()
+            .map(|(_, v)| *v)
+            .collect::<Vec<u64>>();
+        assert_eq!(res, vec![633, 617, 592, 664]);
+        let query = SymbolQuery::parse("*:exactly:2:sum").unwrap();
+        assert_eq!(
+            run_query(test_input, &["--query=*:exactly:2:sum".to_string()]),
+            Some("1855".to_string())
+        );
+        assert_eq!(schematic.query(&query).len(), 2);
+        let isolated = schematic
+            .isolated_numbers()
+            .iter()
+            .map(|n| n.value)
+            .collect::<Vec<u32>>();
+        assert_eq!(isolated, vec![114, 58]);
+        assert!(SymbolQuery::parse("*:exactly:two:sum").is_err());
+    }
 }
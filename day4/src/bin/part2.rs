@@ -5,51 +5,98 @@ use nom::{
     sequence::preceded,
     IResult,
 };
+use std::collections::HashSet;
+
 #[derive(Debug)]
 struct Card {
-    _id: usize,
-    winning: Vec<usize>,
+    id: usize,
+    winning: HashSet<usize>,
     numbers: Vec<usize>,
-    num_instances: usize,
 }
 
 impl Card {
     fn get_matches(&self) -> usize {
-        let num_wins = self
-            .numbers
+        self.numbers
             .iter()
             .filter(|x| self.winning.contains(x))
-            .count();
-        return num_wins;
+            .count()
+    }
+}
+
+#[derive(Debug)]
+struct Cascade {
+    ids: Vec<usize>,
+    matches: Vec<usize>,
+    instances: Vec<usize>,
+}
+
+impl Cascade {
+    // Every card with m matches adds its instances to the next m cards. Each of those ranges is
+    // recorded in a difference array so the whole cascade is a single pass over the cards
+    fn new(cards: &[Card]) -> Cascade {
+        let n = cards.len();
+        let matches = cards
+            .iter()
+            .map(|c| c.get_matches())
+            .collect::<Vec<usize>>();
+        let mut diff = vec![0_i64; n + 1];
+        let mut instances = Vec::with_capacity(n);
+        let mut running = 0;
+        for i in 0..n {
+            running += diff[i];
+            let count = 1 + running as usize;
+            instances.push(count);
+            let end = (i + matches[i] + 1).min(n);
+            if i + 1 < end {
+                diff[i + 1] += count as i64;
+                diff[end] -= count as i64;
+            }
+        }
+        Cascade {
+            ids: cards.iter().map(|c| c.id).collect(),
+            matches,
+            instances,
+        }
     }
 
-    fn add_instance(&mut self) {
-        self.num_instances += 1;
+    fn total(&self) -> usize {
+        self.instances.iter().sum()
+    }
+
+    // (card id, copies contributed) for every earlier card that won copies of the card at idx
+    fn provenance(&self, idx: usize) -> Vec<(usize, usize)> {
+        (0..idx)
+            .filter(|&i| i + self.matches[i] >= idx)
+            .map(|i| (self.ids[i], self.instances[i]))
+            .collect()
     }
 }
 
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part1(input);
-    println!("Result: {}", result);
-}
-
-fn part1(input: &str) -> String {
-    let mut cards = parse_cards(input).expect("Should parse cards");
-    let mut sum = 0;
-    let card_count = cards.1.len();
-
-    for i in 0..card_count {
-        let num_matches = cards.1[i].get_matches();
-        for j in 1..num_matches + 1 {
-            for _ in 0..cards.1[i].num_instances {
-                cards.1[i + j].add_instance();
-            }
+    // Optionally explain where the copies of a card came from, e.g. --explain=10
+    if let Some(id) = std::env::args().find_map(|a| a.strip_prefix("--explain=").map(String::from))
+    {
+        let id = id.parse::<usize>().expect("Should parse card id");
+        let cards = parse_cards(input).expect("Should parse cards").1;
+        let cascade = Cascade::new(&cards);
+        let idx = cascade
+            .ids
+            .iter()
+            .position(|x| *x == id)
+            .expect("Card does not exist");
+        println!("Card {} has {} instances", id, cascade.instances[idx]);
+        for (source, copies) in cascade.provenance(idx) {
+            println!("  {} copies from card {}", copies, source);
         }
-        sum += cards.1[i].num_instances;
     }
+    let result: String = part2(input);
+    println!("Result: {}", result);
+}
 
-    return sum.to_string();
+fn part2(input: &str) -> String {
+    let cards = parse_cards(input).expect("Should parse cards").1;
+    Cascade::new(&cards).total().to_string()
 }
 
 fn parse_cards(input: &str) -> IResult<&str, Vec<Card>> {
@@ -80,10 +127,9 @@ fn card(input: &str) -> IResult<&str, Card> {
     return Ok((
         part2,
         Card {
-            _id: id.parse::<usize>().unwrap(),
+            id: id.parse::<usize>().unwrap(),
             winning: winning.iter().map(|x| *x as usize).collect(),
             numbers: numbers.iter().map(|x| *x as usize).collect(),
-            num_instances: 1,
         },
     ));
 }
@@ -100,7 +146,13 @@ Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
 Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
 Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
 Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
-        let result: String = part1(test_input);
+        let result: String = part2(test_input);
         assert_eq!(result, "30".to_string());
+
+        let cards = parse_cards(test_input).unwrap().1;
+        let cascade = Cascade::new(&cards);
+        assert_eq!(cascade.instances, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(cascade.provenance(4), vec![(1, 1), (3, 4), (4, 8)]);
+        assert!(cascade.provenance(5).is_empty());
     }
 }
@@ -1,17 +1,20 @@
 use nom::{
-    bytes::complete::{tag, take_until},
-    character::complete::{line_ending, space1, u64},
+    bytes::complete::tag,
+    character::complete::{alpha1, line_ending, multispace0, space1, u64},
     multi::{many1, separated_list0},
-    sequence::{preceded, tuple},
+    sequence::{preceded, separated_pair, tuple},
     IResult, Parser,
 };
 
+use std::collections::{HashSet, VecDeque};
 use std::ops::Range;
 
 use nom_supreme::parser_ext::ParserExt;
 
 #[derive(Debug)]
 struct Map {
+    source: String,
+    destination: String,
     maps: Vec<Vec<Range<u64>>>,
 }
 
@@ -37,17 +40,99 @@ impl Map {
     }
 }
 
+// The maps keyed by the categories they convert between
+#[derive(Debug)]
+struct Almanac {
+    maps: Vec<Map>,
+}
+
+impl Almanac {
+    // Maps to apply in order to get from source to target
+    fn path(&self, source: &str, target: &str) -> Option<Vec<&Map>> {
+        let mut queue = VecDeque::new();
+        let mut visited = HashSet::new();
+        queue.push_back((source, Vec::new()));
+        while let Some((curr, path)) = queue.pop_front() {
+            if curr == target {
+                return Some(path);
+            }
+            if !visited.insert(curr) {
+                continue;
+            }
+            self.maps.iter().filter(|m| m.source == curr).for_each(|m| {
+                let mut new_path = path.clone();
+                new_path.push(m);
+                queue.push_back((m.destination.as_str(), new_path));
+            });
+        }
+        None
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.maps.iter().for_each(|map| {
+            let name = format!("{}-to-{}", map.source, map.destination);
+            let mut sources = map.maps.iter().map(|m| m[0].clone()).collect::<Vec<_>>();
+            sources.sort_by_key(|r| r.start);
+            sources.windows(2).for_each(|w| {
+                if w[1].start < w[0].end {
+                    errors.push(format!(
+                        "{}: source ranges {:?} and {:?} overlap",
+                        name, w[0], w[1]
+                    ));
+                }
+            });
+            if self.maps.iter().filter(|m| m.source == map.source).count() > 1 {
+                errors.push(format!("{}: {} has more than one map", name, map.source));
+            }
+            if map.source != "seed" && !self.maps.iter().any(|m| m.destination == map.source) {
+                errors.push(format!("{}: nothing maps to {}", name, map.source));
+            }
+        });
+        if self.path("seed", "location").is_none() {
+            errors.push("No chain of maps from seed to location".to_string());
+        }
+        errors.dedup();
+        errors
+    }
+
+    fn convert(&self, source: &str, target: &str, input: u64) -> Option<u64> {
+        let path = self.path(source, target)?;
+        Some(path.iter().fold(input, |s, map| map.get_res(s)))
+    }
+}
+
 fn main() {
     let input: &str = include_str!("./input.txt");
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let (_, (_, maps)) = parse(input).unwrap();
+    let almanac = Almanac { maps };
+    if args.iter().any(|a| a == "--validate") {
+        almanac.validate().iter().for_each(|e| println!("{}", e));
+    }
+    // Convert a value between any two categories, e.g. --convert=soil:humidity:14
+    if let Some(query) = args.iter().find_map(|a| a.strip_prefix("--convert=")) {
+        let query = query.split(':').collect::<Vec<&str>>();
+        let value = query[2].parse::<u64>().expect("Should parse value");
+        match almanac.convert(query[0], query[1], value) {
+            Some(res) => println!("{} {} is {} {}", query[0], value, query[1], res),
+            None => println!("No chain of maps from {} to {}", query[0], query[1]),
+        }
+    }
     let result: String = part1(input);
     println!("Result: {}", result);
 }
 
 fn part1(input: &str) -> String {
     let (_, (seeds, maps)) = parse(input).unwrap();
+    let almanac = Almanac { maps };
     seeds
         .iter()
-        .map(|s| maps.iter().fold(*s, |s, map| map.get_res(s)))
+        .map(|s| {
+            almanac
+                .convert("seed", "location", *s)
+                .expect("Should map seeds to locations")
+        })
         .collect::<Vec<u64>>()
         .iter()
         .min()
@@ -64,12 +149,21 @@ fn parse(input: &str) -> IResult<&str, (Vec<u64>, Vec<Map>)> {
     Ok((input, (seeds, maps)))
 }
 
+// seed-to-soil map:
 fn parse_mapping(input: &str) -> IResult<&str, Map> {
-    let (input, mapping) = take_until("map:")
-        .precedes(tag("map:"))
-        .precedes(many1(line_ending.precedes(parse_line)).map(|m| Map { maps: m }))
+    let (input, (source, destination)) = multispace0
+        .precedes(separated_pair(alpha1, tag("-to-"), alpha1))
+        .terminated(tag(" map:"))
         .parse(input)?;
-    Ok((input, mapping))
+    let (input, maps) = many1(line_ending.precedes(parse_line))(input)?;
+    Ok((
+        input,
+        Map {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            maps,
+        },
+    ))
 }
 
 fn parse_line(input: &str) -> IResult<&str, Vec<Range<u64>>> {
@@ -127,4 +221,68 @@ humidity-to-location map:
         let result: String = part1(test_input);
         assert_eq!(result, "35".to_string());
     }
+
+    #[test]
+    fn category_graph() {
+        // Same maps as above but out of order
+        let test_input: &str = "seeds: 79 14 55 13
+
+humidity-to-location map:
+60 56 37
+56 93 4
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70";
+        assert_eq!(part1(test_input), "35".to_string());
+        let (_, (_, maps)) = parse(test_input).unwrap();
+        let almanac = Almanac { maps };
+        assert!(almanac.validate().is_empty());
+        // soil 81 -> fertilizer 81 -> water 81 -> light 74 -> temperature 78 -> humidity 78
+        assert_eq!(almanac.convert("soil", "humidity", 81), Some(78));
+        assert_eq!(almanac.convert("location", "seed", 81), None);
+
+        let broken: &str = "seeds: 1
+
+seed-to-soil map:
+50 98 2
+52 60 48
+
+water-to-location map:
+0 0 1";
+        let (_, (_, maps)) = parse(broken).unwrap();
+        let almanac = Almanac { maps };
+        assert_eq!(
+            almanac.validate(),
+            vec![
+                "seed-to-soil: source ranges 60..108 and 98..100 overlap".to_string(),
+                "water-to-location: nothing maps to water".to_string(),
+                "No chain of maps from seed to location".to_string(),
+            ]
+        );
+    }
 }
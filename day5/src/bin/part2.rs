@@ -1,24 +1,27 @@
 use nom::{
-    bytes::complete::{tag, take_until},
-    character::complete::{line_ending, space1, u64},
+    bytes::complete::tag,
+    character::complete::{alpha1, line_ending, multispace0, space1, u64},
     multi::{many1, separated_list0},
-    sequence::{preceded, tuple},
+    sequence::{preceded, separated_pair, tuple},
     IResult, Parser,
 };
 
-use indicatif::ParallelProgressIterator;
 use itertools::Itertools;
+use std::collections::{HashSet, VecDeque};
 use std::ops::Range;
 
 use nom_supreme::parser_ext::ParserExt;
-use rayon::prelude::*;
 
 #[derive(Debug, Clone)]
 struct Map {
+    source: String,
+    destination: String,
     maps: Vec<Vec<Range<u64>>>,
 }
 
 impl Map {
+    // Maps a single value. Only used to check transform now
+    #[cfg(test)]
     fn get_res(&self, input: u64) -> u64 {
         let mut res = 0;
         let mut found = false;
@@ -38,10 +41,390 @@ impl Map {
             input
         }
     }
+
+    // Same as get_res but for whole ranges. Each range is split at the mapping boundaries so
+    // only the pieces are mapped and not every value in them
+    fn transform(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut unmapped = ranges.to_vec();
+        let mut res = Vec::new();
+        // Later mappings take priority like in get_res
+        self.maps.iter().rev().for_each(|m| {
+            let (source, destination) = (&m[0], &m[1]);
+            let mut remaining = Vec::new();
+            unmapped.iter().for_each(|r| {
+                let start = r.start.max(source.start);
+                let end = r.end.min(source.end);
+                if start >= end {
+                    remaining.push(r.clone());
+                    return;
+                }
+                res.push(
+                    (destination.start + (start - source.start))
+                        ..(destination.start + (end - source.start)),
+                );
+                if r.start < start {
+                    remaining.push(r.start..start);
+                }
+                if end < r.end {
+                    remaining.push(end..r.end);
+                }
+            });
+            unmapped = remaining;
+        });
+        res.extend(unmapped);
+        res
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Piece {
+    source: Range<u64>,
+    destination: u64,
+}
+
+impl Piece {
+    fn apply(&self, input: u64) -> u64 {
+        self.destination + (input - self.source.start)
+    }
+
+    fn is_identity(&self) -> bool {
+        self.source.start == self.destination
+    }
+}
+
+// A whole chain of maps as one function. Pieces are sorted by source and together cover every u64
+// below u64::MAX. No Range<u64> can contain u64::MAX so it always maps to itself
+#[derive(Debug, Clone, PartialEq)]
+struct Piecewise {
+    pieces: Vec<Piece>,
+}
+
+impl Piecewise {
+    fn identity() -> Piecewise {
+        Piecewise {
+            pieces: vec![Piece {
+                source: 0..u64::MAX,
+                destination: 0,
+            }],
+        }
+    }
+
+    fn from_map(map: &Map) -> Piecewise {
+        let mut res = Piecewise::identity();
+        // Later mappings take priority like in get_res
+        map.maps.iter().for_each(|m| {
+            res.overlay(m[0].clone(), m[1].start);
+        });
+        res
+    }
+
+    // Replace the function on source with x -> destination + (x - source.start)
+    fn overlay(&mut self, source: Range<u64>, destination: u64) {
+        let mut pieces = Vec::new();
+        self.pieces.iter().for_each(|p| {
+            if p.source.end <= source.start || source.end <= p.source.start {
+                pieces.push(p.clone());
+                return;
+            }
+            if p.source.start < source.start {
+                pieces.push(Piece {
+                    source: p.source.start..source.start,
+                    destination: p.destination,
+                });
+            }
+            if source.end < p.source.end {
+                pieces.push(Piece {
+                    source: source.end..p.source.end,
+                    destination: p.apply(source.end),
+                });
+            }
+        });
+        pieces.push(Piece {
+            source,
+            destination,
+        });
+        self.pieces = pieces;
+        self.normalise();
+    }
+
+    // Sort by source and join neighbouring pieces that continue the same line
+    fn normalise(&mut self) {
+        self.pieces.sort_by_key(|p| p.source.start);
+        let mut pieces: Vec<Piece> = Vec::new();
+        self.pieces.iter().for_each(|p| match pieces.last_mut() {
+            Some(last)
+                if last.source.end == p.source.start
+                    && last.destination + (last.source.end - last.source.start)
+                        == p.destination =>
+            {
+                last.source.end = p.source.end;
+            }
+            _ => pieces.push(p.clone()),
+        });
+        self.pieces = pieces;
+    }
+
+    // First self then next
+    fn compose(&self, next: &Piecewise) -> Piecewise {
+        let mut pieces = Vec::new();
+        self.pieces.iter().for_each(|p| {
+            let image = p.destination..p.apply(p.source.end - 1) + 1;
+            next.pieces.iter().for_each(|q| {
+                let start = image.start.max(q.source.start);
+                let end = image.end.min(q.source.end);
+                if start < end {
+                    let offset = start - p.destination;
+                    pieces.push(Piece {
+                        source: (p.source.start + offset)
+                            ..(p.source.start + offset + (end - start)),
+                        destination: q.apply(start),
+                    });
+                }
+            });
+        });
+        let mut res = Piecewise { pieces };
+        res.normalise();
+        res
+    }
+
+    fn inverse(&self) -> InversePiecewise {
+        let mut pieces = self
+            .pieces
+            .iter()
+            .map(|p| Piece {
+                source: p.destination..p.apply(p.source.end - 1) + 1,
+                destination: p.source.start,
+            })
+            .collect::<Vec<Piece>>();
+        pieces.sort_by_key(|p| p.source.start);
+        InversePiecewise { pieces }
+    }
+
+    fn apply(&self, input: u64) -> u64 {
+        let idx = self.pieces.partition_point(|p| p.source.end <= input);
+        match self.pieces.get(idx) {
+            Some(p) if p.source.contains(&input) => p.apply(input),
+            _ => input,
+        }
+    }
+
+    // Seed ranges that land in the location window
+    fn seeds_for(&self, window: Range<u64>) -> Vec<Range<u64>> {
+        let mut res = self.inverse().transform(&[window]);
+        res.sort_by_key(|r| r.start);
+        res
+    }
+
+    // Smallest seed out of the given seed ranges that lands on location
+    fn first_seed_for(&self, location: u64, seeds: &[Range<u64>]) -> Option<u64> {
+        self.seeds_for(location..location + 1)
+            .iter()
+            .flat_map(|r| {
+                seeds.iter().filter_map(|s| {
+                    let start = r.start.max(s.start);
+                    let end = r.end.min(s.end);
+                    if start < end {
+                        Some(start)
+                    } else {
+                        None
+                    }
+                })
+            })
+            .min()
+    }
+
+    // Same `dest src len` lines as the almanac. Identity pieces are left out since unmapped
+    // values already map to themselves
+    fn dump(&self) -> String {
+        self.pieces
+            .iter()
+            .filter(|p| !p.is_identity())
+            .map(|p| {
+                format!(
+                    "{} {} {}",
+                    p.destination,
+                    p.source.start,
+                    p.source.end - p.source.start
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+// Maps locations back to seeds. This is a relation rather than a function: when two seeds
+// share a location the pieces overlap, and locations nothing maps to are left uncovered
+#[derive(Debug)]
+struct InversePiecewise {
+    pieces: Vec<Piece>,
+}
+
+impl InversePiecewise {
+    fn transform(&self, ranges: &[Range<u64>]) -> Vec<Range<u64>> {
+        let mut res = Vec::new();
+        ranges.iter().for_each(|r| {
+            self.pieces.iter().for_each(|p| {
+                let start = r.start.max(p.source.start);
+                let end = r.end.min(p.source.end);
+                if start < end {
+                    res.push(p.apply(start)..(p.apply(end - 1) + 1));
+                }
+            });
+        });
+        res
+    }
+}
+
+// The whole seed to location chain
+fn compose_maps(almanac: &Almanac) -> Piecewise {
+    almanac
+        .path("seed", "location")
+        .expect("Should map seeds to locations")
+        .iter()
+        .fold(Piecewise::identity(), |acc, map| {
+            acc.compose(&Piecewise::from_map(map))
+        })
+}
+
+// The maps keyed by the categories they convert between
+#[derive(Debug)]
+struct Almanac {
+    maps: Vec<Map>,
+}
+
+impl Almanac {
+    // Maps to apply in order to get from source to target
+    fn path(&self, source: &str, target: &str) -> Option<Vec<&Map>> {
+        let mut queue = VecDeque::new();
+        let mut visited = HashSet::new();
+        queue.push_back((source, Vec::new()));
+        while let Some((curr, path)) = queue.pop_front() {
+            if curr == target {
+                return Some(path);
+            }
+            if !visited.insert(curr) {
+                continue;
+            }
+            self.maps.iter().filter(|m| m.source == curr).for_each(|m| {
+                let mut new_path = path.clone();
+                new_path.push(m);
+                queue.push_back((m.destination.as_str(), new_path));
+            });
+        }
+        None
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.maps.iter().for_each(|map| {
+            let name = format!("{}-to-{}", map.source, map.destination);
+            let mut sources = map.maps.iter().map(|m| m[0].clone()).collect::<Vec<_>>();
+            sources.sort_by_key(|r| r.start);
+            sources.windows(2).for_each(|w| {
+                if w[1].start < w[0].end {
+                    errors.push(format!(
+                        "{}: source ranges {:?} and {:?} overlap",
+                        name, w[0], w[1]
+                    ));
+                }
+            });
+            if self.maps.iter().filter(|m| m.source == map.source).count() > 1 {
+                errors.push(format!("{}: {} has more than one map", name, map.source));
+            }
+            if map.source != "seed" && !self.maps.iter().any(|m| m.destination == map.source) {
+                errors.push(format!("{}: nothing maps to {}", name, map.source));
+            }
+        });
+        if self.path("seed", "location").is_none() {
+            errors.push("No chain of maps from seed to location".to_string());
+        }
+        errors.dedup();
+        errors
+    }
+
+    fn convert(
+        &self,
+        source: &str,
+        target: &str,
+        ranges: &[Range<u64>],
+    ) -> Option<Vec<Range<u64>>> {
+        let path = self.path(source, target)?;
+        Some(
+            path.iter()
+                .fold(ranges.to_vec(), |ranges, map| map.transform(&ranges)),
+        )
+    }
 }
 
 fn main() {
     let input: &str = include_str!("./input.txt");
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if args.iter().any(|a| a == "--validate") {
+        let (_, (_, maps)) = parse(input).unwrap();
+        let almanac = Almanac { maps };
+        almanac.validate().iter().for_each(|e| println!("{}", e));
+    }
+    // Convert a range between any two categories, e.g. --convert=soil:humidity:0..50
+    if let Some(query) = args.iter().find_map(|a| a.strip_prefix("--convert=")) {
+        let query = query.split(':').collect::<Vec<&str>>();
+        let (start, end) = query[2]
+            .split_once("..")
+            .expect("Range should be start..end");
+        let range = start.parse::<u64>().unwrap()..end.parse::<u64>().unwrap();
+        let (_, (_, maps)) = parse(input).unwrap();
+        let almanac = Almanac { maps };
+        match almanac.convert(query[0], query[1], &[range]) {
+            Some(res) => println!("{}: {:?}", query[1], res),
+            None => println!("No chain of maps from {} to {}", query[0], query[1]),
+        }
+        return;
+    }
+    if args.iter().any(|a| a == "--dump") {
+        let (_, (_, maps)) = parse(input).unwrap();
+        println!(
+            "seed-to-location map:\n{}",
+            compose_maps(&Almanac { maps }).dump()
+        );
+        return;
+    }
+    // Seed ranges that land in a location window, e.g. --window=46..57
+    if let Some(window) = args.iter().find_map(|a| a.strip_prefix("--window=")) {
+        let (start, end) = window
+            .split_once("..")
+            .expect("Window should be start..end");
+        let window = start.parse::<u64>().unwrap()..end.parse::<u64>().unwrap();
+        let (_, (seeds, maps)) = parse(input).unwrap();
+        let composed = compose_maps(&Almanac { maps });
+        let valid = composed
+            .seeds_for(window)
+            .iter()
+            .flat_map(|r| {
+                seeds
+                    .iter()
+                    .filter(|s| s.start < r.end && r.start < s.end)
+                    .map(|s| r.start.max(s.start)..r.end.min(s.end))
+            })
+            .collect::<Vec<_>>();
+        println!("Seeds: {:?}", valid);
+        return;
+    }
+    // Location of a single seed, e.g. --location-of=79
+    if let Some(seed) = args.iter().find_map(|a| a.strip_prefix("--location-of=")) {
+        let seed = seed.parse::<u64>().unwrap();
+        let (_, (_, maps)) = parse(input).unwrap();
+        println!("Location: {}", compose_maps(&Almanac { maps }).apply(seed));
+        return;
+    }
+    // First valid seed for a location, e.g. --seed-for=46
+    if let Some(location) = args.iter().find_map(|a| a.strip_prefix("--seed-for=")) {
+        let location = location.parse::<u64>().unwrap();
+        let (_, (seeds, maps)) = parse(input).unwrap();
+        match compose_maps(&Almanac { maps }).first_seed_for(location, &seeds) {
+            Some(seed) => println!("Seed: {}", seed),
+            None => println!("No seed lands on location {}", location),
+        }
+        return;
+    }
     let result: String = part2(input);
     println!("Result: {}", result);
 }
@@ -49,15 +432,12 @@ fn main() {
 fn part2(input: &str) -> String {
     let (_, (seeds, maps)) = parse(input).unwrap();
 
-    merge_ranges(seeds)
-        .iter()
-        .flat_map(|s| s.clone())
-        .collect::<Vec<_>>()
-        .par_iter()
-        .progress()
-        .map(|s| maps.iter().fold(*s, |s, map| map.get_res(s)))
-        .collect::<Vec<u64>>()
+    let almanac = Almanac { maps };
+    almanac
+        .convert("seed", "location", &merge_ranges(seeds))
+        .expect("Should map seeds to locations")
         .iter()
+        .map(|r| r.start)
         .min()
         .unwrap()
         .to_string()
@@ -104,12 +484,21 @@ fn get_seeds(seeds: Vec<u64>) -> Vec<Range<u64>> {
         .collect::<Vec<_>>()
 }
 
+// seed-to-soil map:
 fn parse_mapping(input: &str) -> IResult<&str, Map> {
-    let (input, mapping) = take_until("map:")
-        .precedes(tag("map:"))
-        .precedes(many1(line_ending.precedes(parse_line)).map(|m| Map { maps: m }))
+    let (input, (source, destination)) = multispace0
+        .precedes(separated_pair(alpha1, tag("-to-"), alpha1))
+        .terminated(tag(" map:"))
         .parse(input)?;
-    Ok((input, mapping))
+    let (input, maps) = many1(line_ending.precedes(parse_line))(input)?;
+    Ok((
+        input,
+        Map {
+            source: source.to_string(),
+            destination: destination.to_string(),
+            maps,
+        },
+    ))
 }
 
 fn parse_line(input: &str) -> IResult<&str, Vec<Range<u64>>> {
@@ -166,5 +555,81 @@ humidity-to-location map:
 56 93 4";
         let result: String = part2(test_input);
         assert_eq!(result, "46".to_string());
+
+        // Check the range transform against mapping every seed one by one
+        let (_, (seeds, maps)) = parse(test_input).unwrap();
+        let ranges = maps
+            .iter()
+            .fold(seeds.clone(), |ranges, map| map.transform(&ranges));
+        let mut expected = seeds
+            .iter()
+            .flat_map(|s| s.clone())
+            .map(|s| maps.iter().fold(s, |s, map| map.get_res(s)))
+            .collect::<Vec<u64>>();
+        let mut actual = ranges.iter().flat_map(|r| r.clone()).collect::<Vec<u64>>();
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn composed_map() {
+        let test_input: &str = "seeds: 79 14 55 13
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+        let (_, (seeds, maps)) = parse(test_input).unwrap();
+        let composed = compose_maps(&Almanac { maps: maps.clone() });
+        for seed in 0..120 {
+            let expected = maps.iter().fold(seed, |s, map| map.get_res(s));
+            assert_eq!(composed.apply(seed), expected);
+            assert!(composed
+                .seeds_for(expected..expected + 1)
+                .iter()
+                .any(|r| r.contains(&seed)));
+        }
+        assert_eq!(composed.first_seed_for(46, &seeds), Some(82));
+        assert_eq!(composed.first_seed_for(35, &seeds), None);
+
+        // Nothing lands on 15 once 10..20 is moved away, and u64::MAX is never mapped
+        let mut moved = Piecewise::identity();
+        moved.overlay(10..20, 100);
+        assert!(moved.seeds_for(15..16).is_empty());
+        assert_eq!(moved.seeds_for(100..102), vec![10..12, 100..102]);
+        assert_eq!(moved.apply(u64::MAX), u64::MAX);
+
+        // The dump reads back as a single map
+        let dumped = format!("seeds: 0 1\n\nseed-to-location map:\n{}", composed.dump());
+        let (_, (_, maps)) = parse(&dumped).unwrap();
+        assert_eq!(compose_maps(&Almanac { maps }), composed);
     }
 }
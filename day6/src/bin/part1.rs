@@ -5,6 +5,7 @@ use nom::sequence::separated_pair;
 use nom::{IResult, Parser};
 
 use nom_supreme::parser_ext::ParserExt;
+use std::ops::RangeInclusive;
 
 #[derive(Debug)]
 struct Race {
@@ -13,39 +14,191 @@ struct Race {
 }
 
 impl Race {
-    fn calc_ways(&self) -> usize {
-        let speed_increase = 1;
-
-        (0..self.time + 1)
-            .map(|t_hold| {
-                let speed = t_hold * speed_increase;
-                speed * (self.time - t_hold)
-            })
-            .filter(|d| d > &self.distance)
-            .count()
+    fn beats_record(&self, t_hold: u128) -> bool {
+        t_hold * (self.time as u128 - t_hold) > self.distance as u128
     }
+
+    // Hold times that beat the record. t_hold * (time - t_hold) > distance is a downward
+    // parabola so the winners are one interval, symmetric around time / 2. The lower end is
+    // binary searched, the same as part2 where time * time does not fit in a u128
+    fn winning_range(&self) -> Option<RangeInclusive<u128>> {
+        let time = self.time as u128;
+        if !self.beats_record(time / 2) {
+            return None;
+        }
+        // Distance only rises up to time / 2
+        let (mut low, mut high) = (0, time / 2);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.beats_record(mid) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Some(low..=time - low)
+    }
+
+    fn calc_ways(&self) -> u128 {
+        match self.winning_range() {
+            Some(range) => range.end() - range.start() + 1,
+            None => 0,
+        }
+    }
+}
+
+// How the boat responds to holding the button. The puzzle's boat is BoatModel::default()
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BoatModel {
+    acceleration: u128,      // speed gained per millisecond held
+    max_speed: Option<u128>, // speed stops increasing here
+    start_delay: u128,       // milliseconds after release before the boat moves
+}
+
+impl Default for BoatModel {
+    fn default() -> BoatModel {
+        BoatModel {
+            acceleration: 1,
+            max_speed: None,
+            start_delay: 0,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct RaceReport {
+    winning: Option<RangeInclusive<u128>>,
+    ways: u128,
+    optimal_hold: u128,
+    best_distance: u128,
+    margin: u128, // how far the best hold beats the record by
+}
+
+impl BoatModel {
+    fn distance(&self, time: u128, t_hold: u128) -> u128 {
+        let moving = time.saturating_sub(t_hold).saturating_sub(self.start_delay);
+        let mut speed = t_hold * self.acceleration;
+        if let Some(max_speed) = self.max_speed {
+            speed = speed.min(max_speed);
+        }
+        speed * moving
+    }
+
+    // Distance rises then falls with the hold time: a parabola until the speed cap is reached
+    // and a falling line after it. So the best hold is the top of the parabola or where the cap starts
+    fn optimal_hold(&self, time: u128) -> u128 {
+        let moving_time = time.saturating_sub(self.start_delay);
+        let mut candidates = vec![0, moving_time / 2, moving_time.div_ceil(2)];
+        if let Some(max_speed) = self.max_speed {
+            if self.acceleration > 0 {
+                let t_cap = max_speed.div_ceil(self.acceleration);
+                candidates.push(t_cap.saturating_sub(1));
+                candidates.push(t_cap);
+            }
+        }
+        candidates
+            .into_iter()
+            .filter(|t| *t <= time)
+            .max_by_key(|t| (self.distance(time, *t), std::cmp::Reverse(*t)))
+            .unwrap()
+    }
+
+    fn analyse(&self, race: &Race) -> RaceReport {
+        let time = race.time as u128;
+        let record = race.distance as u128;
+        let optimal_hold = self.optimal_hold(time);
+        let best_distance = self.distance(time, optimal_hold);
+        let beats = |t: u128| self.distance(time, t) > record;
+        let winning = if beats(optimal_hold) {
+            // Binary search each side of the peak for the last losing hold time
+            let (mut lo, mut hi) = (0, optimal_hold);
+            while lo < hi {
+                let mid = (lo + hi) / 2;
+                if beats(mid) {
+                    hi = mid;
+                } else {
+                    lo = mid + 1;
+                }
+            }
+            let start = lo;
+            let (mut lo, mut hi) = (optimal_hold, time);
+            while lo < hi {
+                let mid = (lo + hi).div_ceil(2);
+                if beats(mid) {
+                    lo = mid;
+                } else {
+                    hi = mid - 1;
+                }
+            }
+            Some(start..=lo)
+        } else {
+            None
+        };
+        RaceReport {
+            ways: winning.as_ref().map_or(0, |r| r.end() - r.start() + 1),
+            winning,
+            optimal_hold,
+            best_distance,
+            margin: best_distance.saturating_sub(record),
+        }
+    }
+}
+
+// --acceleration=2 --max-speed=10 --delay=1. Anything left out keeps the puzzle's value
+fn parse_model(args: &[String]) -> Option<BoatModel> {
+    let get = |name: &str| {
+        args.iter()
+            .find_map(|a| a.strip_prefix(name))
+            .map(|v| v.parse::<u128>().expect("Should parse model parameter"))
+    };
+    let acceleration = get("--acceleration=");
+    let max_speed = get("--max-speed=");
+    let start_delay = get("--delay=");
+    if acceleration.is_none() && max_speed.is_none() && start_delay.is_none() {
+        return None;
+    }
+    let default = BoatModel::default();
+    Some(BoatModel {
+        acceleration: acceleration.unwrap_or(default.acceleration),
+        max_speed,
+        start_delay: start_delay.unwrap_or(default.start_delay),
+    })
 }
 
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part1(input);
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let result: String = match parse_model(&args) {
+        Some(model) => {
+            let reports = parse_races(input)
+                .iter()
+                .map(|race| model.analyse(race))
+                .collect::<Vec<RaceReport>>();
+            reports.iter().for_each(|r| println!("{:?}", r));
+            reports.iter().map(|r| r.ways).product::<u128>().to_string()
+        }
+        None => part1(input),
+    };
     println!("Result: {}", result);
 }
 
-fn part1(input: &str) -> String {
+fn parse_races(input: &str) -> Vec<Race> {
     let (_, (time, distance)) = parse(input).expect("should parse");
-    let races: Vec<_> = time
-        .iter()
+    time.iter()
         .zip(distance.iter())
         .map(|(t, d)| Race {
             time: *t,
             distance: *d,
         })
-        .collect();
+        .collect()
+}
+
+fn part1(input: &str) -> String {
+    let races = parse_races(input);
     races
         .iter()
         .map(|r| r.calc_ways())
-        .fold(1, |acc, r| acc * r)
+        .product::<u128>()
         .to_string()
 }
 
@@ -75,4 +228,36 @@ Distance:  9  40  200";
         let result: String = part1(test_input);
         assert_eq!(result, "288".to_string());
     }
+
+    #[test]
+    fn boat_model() {
+        let races = parse_races("Time:      7  15   30\nDistance:  9  40  200");
+        // The default model is the puzzle's boat
+        races.iter().for_each(|race| {
+            let report = BoatModel::default().analyse(race);
+            assert_eq!(report.winning, race.winning_range());
+            assert_eq!(report.ways, race.calc_ways());
+        });
+        let report = BoatModel::default().analyse(&races[0]);
+        assert_eq!(report.optimal_hold, 3);
+        assert_eq!(report.best_distance, 12);
+        assert_eq!(report.margin, 3);
+
+        let model = BoatModel {
+            acceleration: 2,
+            max_speed: Some(5),
+            start_delay: 1,
+        };
+        // Holds 0..=6 give speeds 0 2 4 5 5 5 5 over 6 5 4 3 2 1 0 moving ms = 0 10 16 15 10 5 0
+        let report = model.analyse(&races[0]);
+        assert_eq!(report.optimal_hold, 2);
+        assert_eq!(report.best_distance, 16);
+        assert_eq!(report.winning, Some(1..=4));
+
+        let model = BoatModel {
+            start_delay: 7,
+            ..BoatModel::default()
+        };
+        assert_eq!(model.analyse(&races[0]).winning, None);
+    }
 }
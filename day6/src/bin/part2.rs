@@ -1,28 +1,53 @@
 use nom::bytes::complete::{tag, take_until};
-use nom::character::complete::{multispace0, space1, u64};
+use nom::character::complete::{digit1, multispace0, space1};
 use nom::multi::separated_list0;
 use nom::sequence::separated_pair;
 use nom::{IResult, Parser};
 
 use nom_supreme::parser_ext::ParserExt;
+use std::ops::RangeInclusive;
 
 #[derive(Debug)]
 struct Race {
-    time: u64,
-    distance: u64,
+    time: u128,
+    distance: u128,
 }
 
 impl Race {
-    fn calc_ways(&self) -> usize {
-        let speed_increase = 1;
+    // A product too big for u128 is bigger than any record
+    fn beats_record(&self, t_hold: u128) -> bool {
+        t_hold
+            .checked_mul(self.time - t_hold)
+            .is_none_or(|d| d > self.distance)
+    }
+
+    // Hold times that beat the record. t_hold * (time - t_hold) > distance is a downward
+    // parabola so the winners are one interval, symmetric around time / 2. time * time does
+    // not fit in a u128 for long races so the lower end is binary searched instead of taken
+    // from the quadratic formula
+    fn winning_range(&self) -> Option<RangeInclusive<u128>> {
+        let time = self.time;
+        if !self.beats_record(time / 2) {
+            return None;
+        }
+        // Distance only rises up to time / 2
+        let (mut low, mut high) = (0, time / 2);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.beats_record(mid) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        Some(low..=time - low)
+    }
 
-        (0..self.time + 1)
-            .map(|t_hold| {
-                let speed = t_hold * speed_increase;
-                speed * (self.time - t_hold)
-            })
-            .filter(|d| d > &self.distance)
-            .count()
+    fn calc_ways(&self) -> u128 {
+        match self.winning_range() {
+            Some(range) => range.end() - range.start() + 1,
+            None => 0,
+        }
     }
 }
 
@@ -34,31 +59,29 @@ fn main() {
 
 fn part2(input: &str) -> String {
     let (_, (time, distance)) = parse(input).expect("should parse");
-    let race = Race {
-        time: time,
-        distance: distance,
-    };
+    let race = Race { time, distance };
     race.calc_ways().to_string()
 }
 
-fn parse(input: &str) -> IResult<&str, (u64, u64)> {
+fn parse(input: &str) -> IResult<&str, (u128, u128)> {
     let (input, (time, distance)) =
         separated_pair(parse_line, multispace0, parse_line).parse(input)?;
     Ok((input, (time, distance)))
 }
 
-fn parse_line(input: &str) -> IResult<&str, u64> {
+// The spaces between numbers are ignored so the digits are joined as strings
+fn parse_line(input: &str) -> IResult<&str, u128> {
     let (input, numbers) = take_until(":")
         .precedes(tag(":"))
         .precedes(multispace0)
-        .precedes(separated_list0(space1, u64))
+        .precedes(separated_list0(space1, digit1))
         .parse(input)?;
     Ok((
         input,
-        numbers.iter().fold(0, |acc, n| {
-            let num_digit = (*n as f32).log10() as u32 + 1;
-            acc * 10_u64.pow(num_digit) + n
-        }),
+        numbers
+            .concat()
+            .parse::<u128>()
+            .expect("Should fit in u128"),
     ))
 }
 
@@ -73,4 +96,52 @@ Distance:  9  40  200";
         let result: String = part2(test_input);
         assert_eq!(result, "71503".to_string());
     }
+
+    #[test]
+    fn exact_bounds() {
+        // Holding for 4 or 6 only ties the record
+        assert_eq!(
+            Race {
+                time: 10,
+                distance: 24
+            }
+            .winning_range(),
+            Some(5..=5)
+        );
+        assert_eq!(
+            Race {
+                time: 10,
+                distance: 25
+            }
+            .winning_range(),
+            None
+        );
+        assert_eq!(
+            Race {
+                time: 7,
+                distance: 9
+            }
+            .winning_range(),
+            Some(2..=5)
+        );
+        let race = Race {
+            time: 1_000_000_000_000,
+            distance: 1,
+        };
+        assert_eq!(race.calc_ways(), 999_999_999_999);
+        let race = Race {
+            time: 1_000_000_000_000,
+            distance: 249_999_999_999_999_999_999_999,
+        };
+        assert_eq!(
+            race.winning_range(),
+            Some(500_000_000_000..=500_000_000_000)
+        );
+        assert_eq!(part2("Time: 7 0 3\nDistance: 1 0 0"), "702".to_string());
+        // time * time would overflow a u128 here
+        assert_eq!(
+            part2("Time: 1234567 8901234 5678901\nDistance: 1"),
+            "123456789012345678900".to_string()
+        );
+    }
 }
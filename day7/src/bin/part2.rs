@@ -10,23 +10,105 @@ use nom::{
 
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part2(input);
+    let mut args = std::env::args().skip(1).collect::<Vec<String>>();
+    if let Some(idx) = args.iter().position(|a| a == "--explain") {
+        args.remove(idx);
+        let rules = RuleSet::from_args(&args, RuleSet::jokers());
+        explain(input, &rules)
+            .iter()
+            .for_each(|e| println!("{}", e));
+    }
+    // Any rule arguments change the rules, otherwise the puzzle's rules are used
+    let result: String = if args.is_empty() {
+        part2(input)
+    } else {
+        winnings(input, &RuleSet::from_args(&args, RuleSet::jokers()))
+    };
     println!("Result: {}", result);
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    FullHouse,
+    FourOfAKind,
+    FiveOfAKind,
+}
+
+// How to order two hands of the same type
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TieBreak {
+    Positional,       // first card that differs, left to right
+    SortedDescending, // strongest card first, then the next strongest
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct RuleSet {
+    ranking: Vec<char>,          // strongest first
+    wildcards: Vec<char>,        // count as whatever makes the best hand
+    tie_break: Option<TieBreak>, // None leaves hands of the same type tied
+}
+
+impl RuleSet {
+    fn new(ranking: &str, wildcards: &str, tie_break: Option<TieBreak>) -> RuleSet {
+        RuleSet {
+            ranking: ranking.chars().collect(),
+            wildcards: wildcards.chars().collect(),
+            tie_break,
+        }
+    }
+
+    // J is a joker and the weakest card
+    fn jokers() -> RuleSet {
+        RuleSet::new("AKQT98765432J", "J", Some(TieBreak::Positional))
+    }
+
+    // --ranking=AKQJT98765432 --wild=J --tie-break=positional|sorted|none
+    fn from_args(args: &[String], default: RuleSet) -> RuleSet {
+        let mut rules = default;
+        args.iter().for_each(|a| {
+            if let Some(ranking) = a.strip_prefix("--ranking=") {
+                rules.ranking = ranking.chars().collect();
+            } else if let Some(wild) = a.strip_prefix("--wild=") {
+                rules.wildcards = wild.chars().collect();
+            } else if let Some(tie_break) = a.strip_prefix("--tie-break=") {
+                rules.tie_break = match tie_break {
+                    "positional" => Some(TieBreak::Positional),
+                    "sorted" => Some(TieBreak::SortedDescending),
+                    "none" => None,
+                    _ => panic!("Invalid tie break: {}", tie_break),
+                };
+            }
+        });
+        rules
+    }
+
+    // Higher is stronger
+    fn strength(&self, card: &Card) -> usize {
+        let position = self
+            .ranking
+            .iter()
+            .position(|&x| x == card.field)
+            .unwrap_or_else(|| panic!("Card {} is not ranked", card.field));
+        self.ranking.len() - position
+    }
+
+    fn is_wild(&self, card: &Card) -> bool {
+        self.wildcards.contains(&card.field)
+    }
+}
+
 #[derive(Debug)]
 struct Card {
     field: char,
 }
 
 impl Card {
-    fn compare(&self, other: &Card) -> Ordering {
-        let orders = [
-            'A', 'K', 'Q', 'T', '9', '8', '7', '6', '5', '4', '3', '2', 'J',
-        ];
-        let self_order = orders.iter().position(|&x| x == self.field).unwrap();
-        let other_order = orders.iter().position(|&x| x == other.field).unwrap();
-        self_order.cmp(&other_order).reverse()
+    fn compare(&self, other: &Card, rules: &RuleSet) -> Ordering {
+        rules.strength(self).cmp(&rules.strength(other))
     }
 }
 
@@ -44,89 +126,147 @@ impl Hand {
         })
     }
 
-    fn score(&self) -> usize {
-        let non_jokers = self
+    // Group sizes from largest to smallest, e.g. [3, 2] for a full house, after the wildcards
+    // have joined the largest group. Also returns the card the wildcards copied.
+    // Comparing these signatures orders hands of any size, and for five cards it matches HandType
+    fn signature(&self, rules: &RuleSet) -> (Vec<usize>, Option<char>) {
+        let non_wild = self
             .cards
             .iter()
-            .filter(|c| c.field != 'J')
+            .filter(|c| !rules.is_wild(c))
             .map(|c| c.field)
             .collect::<Vec<char>>();
-        let num_jokers = 5 - non_jokers.len();
-        let mut char_map = self.into_character_map(non_jokers);
-        let largest_key = char_map
-            .iter()
-            .max_by_key(|&(_, v)| v)
-            .map(|(k, _)| k.clone())
-            .or(Some('J'))
-            .unwrap();
-
-        *char_map.entry(largest_key).or_insert(0) += num_jokers;
-
-        let counts = char_map.values().collect::<Vec<&usize>>();
-
-        if counts.len() == 5 {
-            // High Card
-            0
-        } else if counts.len() == 4 {
-            // 1 pair
-            1
-        } else if counts.len() == 3 {
-            // 3 unique cards
-            if counts.iter().filter(|&&c| *c == 2).count() == 2 {
-                // 2 pairs
-                2
-            } else {
-                // 3 of a kind
-                3
-            }
-        } else if counts.len() == 2 {
-            // 2 unique cards
-            if counts.iter().max().unwrap() == &&3 {
-                // full house
-                4
-            } else {
-                // 4 of a kind
-                5
-            }
-        } else {
-            // 1 unique card = 5 of a kind
-            6
+        let num_wild = self.cards.len() - non_wild.len();
+        let mut groups = self
+            .into_character_map(non_wild)
+            .into_iter()
+            .collect::<Vec<(char, usize)>>();
+        // Largest group first and the stronger card when two groups are the same size
+        groups.sort_by(|a, b| {
+            b.1.cmp(&a.1).then(
+                rules
+                    .strength(&Card { field: b.0 })
+                    .cmp(&rules.strength(&Card { field: a.0 })),
+            )
+        });
+        // Wildcards are best spent on the largest group. A hand of only wildcards copies the strongest card
+        let substitute = match groups.first() {
+            Some((c, _)) => *c,
+            None => rules.ranking[0],
+        };
+        let mut counts = groups.iter().map(|(_, n)| *n).collect::<Vec<usize>>();
+        match counts.first_mut() {
+            Some(largest) => *largest += num_wild,
+            None if num_wild > 0 => counts.push(num_wild),
+            None => {}
         }
+        (counts, if num_wild > 0 { Some(substitute) } else { None })
     }
 
-    fn compare(&self, other: &Hand) -> Ordering {
-        let self_score = self.score();
-        let other_score = other.score();
-        if self_score == other_score {
-            // compare each card until first difference
-            for i in 0..self.cards.len() {
-                let self_card = &self.cards[i];
-                let other_card = &other.cards[i];
-                let card_order = self_card.compare(other_card);
-                if card_order != Ordering::Equal {
-                    return card_order;
-                } else {
-                    continue;
-                }
+    fn hand_type(&self, rules: &RuleSet) -> HandType {
+        let (counts, _) = self.signature(rules);
+        match (counts.first().unwrap_or(&0), counts.get(1)) {
+            (c, _) if *c >= 5 => HandType::FiveOfAKind,
+            (4, _) => HandType::FourOfAKind,
+            (3, Some(c)) if *c >= 2 => HandType::FullHouse,
+            (3, _) => HandType::ThreeOfAKind,
+            (2, Some(2)) => HandType::TwoPair,
+            (2, _) => HandType::OnePair,
+            _ => HandType::HighCard,
+        }
+    }
+
+    fn compare(&self, other: &Hand, rules: &RuleSet) -> Ordering {
+        let type_order = self.signature(rules).0.cmp(&other.signature(rules).0);
+        if type_order != Ordering::Equal {
+            return type_order;
+        }
+        match rules.tie_break {
+            Some(TieBreak::Positional) => self
+                .cards
+                .iter()
+                .zip(other.cards.iter())
+                .map(|(a, b)| a.compare(b, rules))
+                .find(|o| *o != Ordering::Equal)
+                .unwrap_or(Ordering::Equal),
+            Some(TieBreak::SortedDescending) => {
+                let sorted = |hand: &Hand| {
+                    let mut strengths = hand
+                        .cards
+                        .iter()
+                        .map(|c| rules.strength(c))
+                        .collect::<Vec<usize>>();
+                    strengths.sort_by(|a, b| b.cmp(a));
+                    strengths
+                };
+                sorted(self).cmp(&sorted(other))
             }
-            Ordering::Equal
-        } else {
-            self_score.cmp(&other_score)
+            None => Ordering::Equal,
         }
     }
 }
 
-fn part2(input: &str) -> String {
+#[derive(Debug, PartialEq)]
+struct Explanation {
+    cards: String,
+    hand_type: HandType,
+    signature: Vec<usize>,
+    substitute: Option<char>, // card the wildcards count as
+    rank: usize,
+    bid: usize,
+    winnings: usize,
+}
+
+impl std::fmt::Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{:>5} {} {:?} {:?} wild={} bid={} winnings={}",
+            self.rank,
+            self.cards,
+            self.hand_type,
+            self.signature,
+            self.substitute.map_or("-".to_string(), |c| c.to_string()),
+            self.bid,
+            self.winnings
+        )
+    }
+}
+
+// Every hand from weakest to strongest with how it was ranked
+fn explain(input: &str, rules: &RuleSet) -> Vec<Explanation> {
     let (_, mut hands) = parse(input).expect("Failed to parse input");
-    hands.sort_by(|a, b| a.compare(b));
+    hands.sort_by(|a, b| a.compare(b, rules));
     hands
         .iter()
         .enumerate()
-        .map(|(idx, h)| (idx + 1) * h.bid)
+        .map(|(idx, h)| {
+            let (signature, substitute) = h.signature(rules);
+            Explanation {
+                cards: h.cards.iter().map(|c| c.field).collect(),
+                hand_type: h.hand_type(rules),
+                signature,
+                substitute,
+                rank: idx + 1,
+                bid: h.bid,
+                winnings: (idx + 1) * h.bid,
+            }
+        })
+        .collect()
+}
+
+fn winnings(input: &str, rules: &RuleSet) -> String {
+    explain(input, rules)
+        .iter()
+        .map(|e| e.winnings)
         .sum::<usize>()
         .to_string()
 }
 
+fn part2(input: &str) -> String {
+    winnings(input, &RuleSet::jokers())
+}
+
 fn parse(input: &str) -> IResult<&str, Vec<Hand>> {
     separated_list1(line_ending, parse_hand)(input)
 }
@@ -160,4 +300,68 @@ QQQJA 483";
         let result: String = part2(test_input);
         assert_eq!(result, "5905".to_string());
     }
+
+    #[test]
+    fn rule_sets() {
+        let hand = |cards: &str| parse_hand(&format!("{} 1", cards)).unwrap().1;
+        let rules = RuleSet::jokers();
+        assert_eq!(hand("JJJJJ").hand_type(&rules), HandType::FiveOfAKind);
+        assert_eq!(hand("KTJJT").hand_type(&rules), HandType::FourOfAKind);
+        assert!(HandType::FullHouse > HandType::ThreeOfAKind);
+
+        // Two kinds of wildcard
+        let rules = RuleSet::new("AKQJT98765432", "J2", Some(TieBreak::Positional));
+        assert_eq!(hand("2J3A4").hand_type(&rules), HandType::ThreeOfAKind);
+        assert_eq!(hand("22JJ9").hand_type(&rules), HandType::FiveOfAKind);
+
+        let rules = RuleSet::new("AKQJT98765432", "", Some(TieBreak::SortedDescending));
+        assert_eq!(
+            hand("2345A").compare(&hand("KQJT9"), &rules),
+            Ordering::Greater
+        );
+        let rules = RuleSet::new("AKQJT98765432", "", None);
+        assert_eq!(
+            hand("2345A").compare(&hand("KQJT9"), &rules),
+            Ordering::Equal
+        );
+
+        let args = ["--wild=".to_string()];
+        assert_eq!(
+            RuleSet::from_args(&args, RuleSet::jokers()).wildcards,
+            Vec::<char>::new()
+        );
+    }
+
+    #[test]
+    fn any_hand_size() {
+        let hand = |cards: &str| parse_hand(&format!("{} 1", cards)).unwrap().1;
+        let rules = RuleSet::jokers();
+        assert_eq!(hand("JJ").signature(&rules), (vec![2], Some('A')));
+        assert_eq!(hand("KKQQJ").signature(&rules), (vec![3, 2], Some('K')));
+        assert_eq!(
+            hand("KKKQQQ2J").signature(&rules),
+            (vec![4, 3, 1], Some('K'))
+        );
+        assert_eq!(hand("KKKQQQ2J").hand_type(&rules), HandType::FourOfAKind);
+        // Three pairs beat two pairs when hands have seven cards
+        assert_eq!(
+            hand("2233445").compare(&hand("AAKKQT9"), &rules),
+            Ordering::Greater
+        );
+
+        let explained = explain(
+            "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483",
+            &rules,
+        );
+        let top = explained.last().unwrap();
+        assert_eq!(top.cards, "KTJJT");
+        assert_eq!(top.hand_type, HandType::FourOfAKind);
+        assert_eq!(top.substitute, Some('T'));
+        assert_eq!((top.rank, top.winnings), (5, 1100));
+        assert_eq!(explained[0].substitute, None);
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use nom::{
     bytes::complete::{tag, take_until},
@@ -8,59 +8,130 @@ use nom::{
     IResult,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Left,
+    Right,
+}
+
 #[derive(Debug)]
-struct Instruction {
-    orig: Vec<char>,
-    current: Vec<char>,
+struct Instructions {
+    directions: Vec<Direction>,
+}
+
+impl Instructions {
+    // (position in the instructions, direction) forever
+    fn iter(&self) -> impl Iterator<Item = (usize, Direction)> + '_ {
+        self.directions.iter().copied().enumerate().cycle()
+    }
 }
 
-impl Instruction {
-    fn get_instructions(&mut self) -> Vec<char> {
-        if self.current.len() == 0 {
-            self.current = self.orig.clone();
-            self.current.clone()
-        } else {
-            self.current.clone()
+// Nodes are numbered in name order so walking the network never touches the names
+#[derive(Debug)]
+struct Network<'a> {
+    names: Vec<&'a str>,
+    ids: HashMap<&'a str, usize>,
+    children: Vec<(usize, usize)>, // (left, right)
+}
+
+impl<'a> Network<'a> {
+    fn new(nodes: &BTreeMap<&'a str, (&'a str, &'a str)>) -> Network<'a> {
+        let names = nodes.keys().cloned().collect::<Vec<&str>>();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (*name, id))
+            .collect::<HashMap<&str, usize>>();
+        let children = nodes
+            .values()
+            .map(|(left, right)| (ids[left], ids[right]))
+            .collect();
+        Network {
+            names,
+            ids,
+            children,
+        }
+    }
+
+    fn next(&self, id: usize, direction: Direction) -> usize {
+        match direction {
+            Direction::Left => self.children[id].0,
+            Direction::Right => self.children[id].1,
         }
     }
 
-    fn get_next(&mut self) -> Option<char> {
-        let inst = self.get_instructions();
-        self.current = inst[1..].to_vec();
-        inst.first().cloned()
+    // Graphviz DOT with start nodes in green and end nodes in red
+    fn to_dot(&self, is_start: impl Fn(&str) -> bool, is_end: impl Fn(&str) -> bool) -> String {
+        let mut lines = vec!["digraph network {".to_string()];
+        self.names.iter().enumerate().for_each(|(id, name)| {
+            if is_start(name) {
+                lines.push(format!("    \"{}\" [style=filled, fillcolor=green];", name));
+            } else if is_end(name) {
+                lines.push(format!("    \"{}\" [style=filled, fillcolor=red];", name));
+            }
+            let (left, right) = self.children[id];
+            if left == right {
+                lines.push(format!("    \"{}\" -> \"{}\";", name, self.names[left]));
+            } else {
+                lines.push(format!(
+                    "    \"{}\" -> \"{}\" [label=L];",
+                    name, self.names[left]
+                ));
+                lines.push(format!(
+                    "    \"{}\" -> \"{}\" [label=R];",
+                    name, self.names[right]
+                ));
+            }
+        });
+        lines.push("}".to_string());
+        lines.join("\n")
     }
 }
 
 fn main() {
     let input: &str = include_str!("./input.txt");
+    if std::env::args().any(|a| a == "--dot") {
+        let (_, (_, nodes)) = parse(input).expect("Failed to parse input");
+        let network = Network::new(&nodes);
+        println!("{}", network.to_dot(|n| n == "AAA", |n| n == "ZZZ"));
+        return;
+    }
     let result: String = part1(input);
     println!("Result: {}", result);
 }
 
 fn part1(input: &str) -> String {
-    let (_, (mut instructions, nodes)) = parse(input).expect("Failed to parse input");
+    let (_, (instructions, nodes)) = parse(input).expect("Failed to parse input");
+    let network = Network::new(&nodes);
+    let end = network.ids["ZZZ"];
     let mut c = 0;
-    let mut curr = "AAA";
-    while curr != "ZZZ" {
-        let (left, right) = nodes.get(curr).unwrap();
-        let next_inst = instructions.get_next().unwrap();
-        if next_inst == 'R' {
-            curr = *right;
-        } else {
-            curr = *left;
+    let mut curr = network.ids["AAA"];
+    for (_, direction) in instructions.iter() {
+        if curr == end {
+            break;
         }
+        curr = network.next(curr, direction);
         c += 1;
     }
     c.to_string()
 }
 
-fn parse(input: &str) -> IResult<&str, (Instruction, BTreeMap<&str, (&str, &str)>)> {
+fn parse(input: &str) -> IResult<&str, (Instructions, BTreeMap<&str, (&str, &str)>)> {
     let (input, (instructions_str, nodes)) =
         separated_pair(alpha0, multispace0, parse_nodes)(input)?;
-    let instructions_indiv = instructions_str.chars().collect::<Vec<_>>();
-    let instructions = Instruction {
-        orig: instructions_indiv.clone(),
-        current: instructions_indiv,
+    // The walk repeats the instructions forever so there has to be at least one
+    if instructions_str.is_empty() {
+        panic!("Instructions should not be empty");
+    }
+    let instructions = Instructions {
+        directions: instructions_str
+            .chars()
+            .map(|c| match c {
+                'L' => Direction::Left,
+                'R' => Direction::Right,
+                _ => panic!("Invalid instruction: {}", c),
+            })
+            .collect(),
     };
     Ok((input, (instructions, nodes)))
 }
@@ -110,4 +181,26 @@ ZZZ = (ZZZ, ZZZ)";
         let result: String = part1(test_input);
         assert_eq!(result, "6".to_string());
     }
+
+    #[test]
+    fn dot_export() {
+        let test_input: &str = "LLR
+
+AAA = (BBB, BBB)
+BBB = (AAA, ZZZ)
+ZZZ = (ZZZ, ZZZ)";
+        let (_, (_, nodes)) = parse(test_input).unwrap();
+        let network = Network::new(&nodes);
+        assert_eq!(
+            network.to_dot(|n| n == "AAA", |n| n == "ZZZ"),
+            r#"digraph network {
+    "AAA" [style=filled, fillcolor=green];
+    "AAA" -> "BBB";
+    "BBB" -> "AAA" [label=L];
+    "BBB" -> "ZZZ" [label=R];
+    "ZZZ" [style=filled, fillcolor=red];
+    "ZZZ" -> "ZZZ";
+}"#
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use nom::{
     bytes::complete::{tag, take_until},
@@ -8,87 +8,257 @@ use nom::{
     IResult,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Direction {
+    Left,
+    Right,
+}
+
 #[derive(Debug)]
-struct Instruction {
-    orig: Vec<char>,
-    current: Vec<char>,
+struct Instructions {
+    directions: Vec<Direction>,
 }
 
-impl Instruction {
-    fn get_instructions(&mut self) -> Vec<char> {
-        if self.current.len() == 0 {
-            self.current = self.orig.clone();
-            self.current.clone()
-        } else {
-            self.current.clone()
+impl Instructions {
+    // (position in the instructions, direction) forever
+    fn iter(&self) -> impl Iterator<Item = (usize, Direction)> + '_ {
+        self.directions.iter().copied().enumerate().cycle()
+    }
+}
+
+// Nodes are numbered in name order so walking the network never touches the names
+#[derive(Debug)]
+struct Network<'a> {
+    names: Vec<&'a str>,
+    children: Vec<(usize, usize)>, // (left, right)
+}
+
+impl<'a> Network<'a> {
+    fn new(nodes: &BTreeMap<&'a str, (&'a str, &'a str)>) -> Network<'a> {
+        let names = nodes.keys().cloned().collect::<Vec<&str>>();
+        let ids = names
+            .iter()
+            .enumerate()
+            .map(|(id, name)| (*name, id))
+            .collect::<HashMap<&str, usize>>();
+        let children = nodes
+            .values()
+            .map(|(left, right)| (ids[left], ids[right]))
+            .collect();
+        Network { names, children }
+    }
+
+    fn next(&self, id: usize, direction: Direction) -> usize {
+        match direction {
+            Direction::Left => self.children[id].0,
+            Direction::Right => self.children[id].1,
         }
     }
 
-    fn get_next(&mut self) -> Option<char> {
-        let inst = self.get_instructions();
-        self.current = inst[1..].to_vec();
-        inst.first().cloned()
+    // Graphviz DOT with start nodes in green and end nodes in red
+    fn to_dot(&self, is_start: impl Fn(&str) -> bool, is_end: impl Fn(&str) -> bool) -> String {
+        let mut lines = vec!["digraph network {".to_string()];
+        self.names.iter().enumerate().for_each(|(id, name)| {
+            if is_start(name) {
+                lines.push(format!("    \"{}\" [style=filled, fillcolor=green];", name));
+            } else if is_end(name) {
+                lines.push(format!("    \"{}\" [style=filled, fillcolor=red];", name));
+            }
+            let (left, right) = self.children[id];
+            if left == right {
+                lines.push(format!("    \"{}\" -> \"{}\";", name, self.names[left]));
+            } else {
+                lines.push(format!(
+                    "    \"{}\" -> \"{}\" [label=L];",
+                    name, self.names[left]
+                ));
+                lines.push(format!(
+                    "    \"{}\" -> \"{}\" [label=R];",
+                    name, self.names[right]
+                ));
+            }
+        });
+        lines.push("}".to_string());
+        lines.join("\n")
+    }
+}
+
+// Where one ghost is at Z. Steps in prefix happen once, steps in cycle_hits repeat every
+// cycle_len steps once the ghost has entered its cycle at cycle_start
+#[derive(Debug, PartialEq)]
+struct GhostCycle {
+    prefix: Vec<u128>,
+    cycle_start: u128,
+    cycle_len: u128,
+    cycle_hits: Vec<u128>,
+}
+
+impl GhostCycle {
+    fn is_at_end(&self, step: u128) -> bool {
+        if step < self.cycle_start {
+            self.prefix.contains(&step)
+        } else {
+            self.cycle_hits
+                .iter()
+                .any(|r| step >= *r && (step - r).is_multiple_of(self.cycle_len))
+        }
     }
 }
 
 fn main() {
     let input: &str = include_str!("./input.txt");
+    if std::env::args().any(|a| a == "--dot") {
+        let (_, (_, nodes)) = parse(input).expect("Failed to parse input");
+        let network = Network::new(&nodes);
+        println!(
+            "{}",
+            network.to_dot(|n| n.ends_with('A'), |n| n.ends_with('Z'))
+        );
+        return;
+    }
     let result: String = part2(input);
     println!("Result: {}", result);
 }
 
 fn part2(input: &str) -> String {
-    let (_, (mut instructions, nodes)) = parse(input).expect("Failed to parse input");
-    let all_nodes = nodes.keys().cloned().collect::<Vec<_>>();
-
-    let a_nodes = all_nodes
+    let (_, (instructions, nodes)) = parse(input).expect("Failed to parse input");
+    let network = Network::new(&nodes);
+    let cycles = network
+        .names
         .iter()
-        .filter(|x| x.ends_with("A"))
+        .enumerate()
+        .filter(|(_, name)| name.ends_with('A'))
+        .map(|(start, _)| find_cycle(start, &instructions, &network))
         .collect::<Vec<_>>();
 
-    let intervals = a_nodes
+    match first_common_end(&cycles) {
+        Some(steps) => steps.to_string(),
+        None => "No solution".to_string(),
+    }
+}
+
+// The ghost's state is its node and where it is in the instructions. Once a state repeats the
+// walk loops forever
+fn find_cycle(start: usize, instructions: &Instructions, network: &Network) -> GhostCycle {
+    let mut seen = HashMap::new();
+    let mut ends = Vec::new();
+    let mut curr = start;
+    for (step, (idx, direction)) in instructions.iter().enumerate() {
+        let step = step as u128;
+        if let Some(&first) = seen.get(&(curr, idx)) {
+            let (prefix, cycle_hits) = ends.iter().partition(|s| **s < first);
+            return GhostCycle {
+                prefix,
+                cycle_start: first,
+                cycle_len: step - first,
+                cycle_hits,
+            };
+        }
+        seen.insert((curr, idx), step);
+        if network.names[curr].ends_with('Z') {
+            ends.push(step);
+        }
+        curr = network.next(curr, direction);
+    }
+    unreachable!("Instructions cycle forever")
+}
+
+fn first_common_end(cycles: &[GhostCycle]) -> Option<u128> {
+    // Before the last ghost enters its cycle it can only be at one of its prefix steps
+    let latest = cycles.iter().max_by_key(|c| c.cycle_start)?;
+    if let Some(step) = latest
+        .prefix
         .iter()
-        .map(|x| {
-            let mut c = 0;
-            let mut curr = **x;
-            while !curr.ends_with("Z") {
-                let (left, right) = nodes.get(curr).unwrap();
-                let next_inst = instructions.get_next().unwrap();
-                if next_inst == 'R' {
-                    curr = *right;
-                } else {
-                    curr = *left;
-                }
-                c += 1;
-            }
-            c
-        })
-        .collect::<Vec<_>>();
+        .find(|s| cycles.iter().all(|c| c.is_at_end(**s)))
+    {
+        return Some(*step);
+    }
 
-    get_lcm(intervals).to_string()
+    // After that every ghost is in its cycle so solve step = hit (mod cycle_len) for every
+    // ghost, trying each combination of hits
+    let mut best: Option<u128> = None;
+    let mut combinations: Vec<(u128, u128)> = vec![(0, 1)];
+    for cycle in cycles {
+        combinations = combinations
+            .iter()
+            .flat_map(|(a, n)| {
+                cycle
+                    .cycle_hits
+                    .iter()
+                    .filter_map(|r| crt(*a, *n, r % cycle.cycle_len, cycle.cycle_len))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+    }
+    combinations.iter().for_each(|(a, n)| {
+        // Smallest step that is a (mod n) and not before any ghost is in its cycle
+        let step = if *a >= latest.cycle_start {
+            *a
+        } else {
+            a + (latest.cycle_start - a).div_ceil(*n) * n
+        };
+        best = Some(best.map_or(step, |b| b.min(step)));
+    });
+    best
 }
 
-fn get_lcm(intervals: Vec<i32>) -> i64 {
-    let largest = intervals.iter().max().unwrap();
-    let mut curr = *largest as i64;
-    loop {
-        if intervals.iter().all(|x| (curr % (*x as i64) == 0)) {
-            break;
-        }
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
 
-        curr += *largest as i64;
+fn lcm(a: u128, b: u128) -> u128 {
+    a / gcd(a, b) * b
+}
+
+// Merge x = a1 (mod n1) and x = a2 (mod n2) into x = a (mod lcm(n1, n2)). The moduli do not
+// need to be coprime but there is no solution when a1 and a2 disagree modulo their gcd
+fn crt(a1: u128, n1: u128, a2: u128, n2: u128) -> Option<(u128, u128)> {
+    let g = gcd(n1, n2);
+    let diff = (a2 as i128 - a1 as i128).rem_euclid(n2 as i128) as u128;
+    if !diff.is_multiple_of(g) {
+        return None;
     }
+    let l = lcm(n1, n2);
+    // Solve n1 * k = diff (mod n2) for k
+    let m = n2 / g;
+    let k = (diff / g) % m * mod_inverse((n1 / g) % m, m) % m;
+    Some(((a1 + n1 * k) % l, l))
+}
 
-    curr
+fn mod_inverse(a: u128, m: u128) -> u128 {
+    if m == 1 {
+        return 0;
+    }
+    let (mut old_r, mut r) = (a as i128, m as i128);
+    let (mut old_s, mut s) = (1_i128, 0_i128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, old_s - q * s);
+    }
+    old_s.rem_euclid(m as i128) as u128
 }
 
-fn parse(input: &str) -> IResult<&str, (Instruction, BTreeMap<&str, (&str, &str)>)> {
+fn parse(input: &str) -> IResult<&str, (Instructions, BTreeMap<&str, (&str, &str)>)> {
     let (input, (instructions_str, nodes)) =
         separated_pair(alphanumeric0, multispace0, parse_nodes)(input)?;
-    let instructions_indiv = instructions_str.chars().collect::<Vec<_>>();
-    let instructions = Instruction {
-        orig: instructions_indiv.clone(),
-        current: instructions_indiv,
+    // The walk repeats the instructions forever so there has to be at least one
+    if instructions_str.is_empty() {
+        panic!("Instructions should not be empty");
+    }
+    let instructions = Instructions {
+        directions: instructions_str
+            .chars()
+            .map(|c| match c {
+                'L' => Direction::Left,
+                'R' => Direction::Right,
+                _ => panic!("Invalid instruction: {}", c),
+            })
+            .collect(),
     };
     Ok((input, (instructions, nodes)))
 }
@@ -129,4 +299,101 @@ XXX = (XXX, XXX)";
         let result: String = part2(test_input);
         assert_eq!(result, "6".to_string());
     }
+
+    #[test]
+    fn ghost_cycles() {
+        let test_input: &str = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+        let (_, (instructions, nodes)) = parse(test_input).unwrap();
+        let network = Network::new(&nodes);
+        let cycle = find_cycle(
+            network.names.binary_search(&"22A").unwrap(),
+            &instructions,
+            &network,
+        );
+        assert_eq!(
+            cycle,
+            GhostCycle {
+                prefix: vec![],
+                cycle_start: 1,
+                cycle_len: 6,
+                cycle_hits: vec![3, 6],
+            }
+        );
+
+        assert_eq!(crt(2, 4, 3, 6), None);
+        assert_eq!(crt(1, 4, 3, 6), Some((9, 12)));
+        assert_eq!(crt(0, 1, 5, 7), Some((5, 7)));
+
+        // The first Z is not where the cycle repeats and this ghost never reaches Z again
+        let test_input: &str = "L
+
+AAA = (BBZ, BBZ)
+BBZ = (CCC, CCC)
+CCC = (CCC, CCC)
+11A = (11Z, 11Z)
+11Z = (11Z, 11Z)";
+        assert_eq!(part2(test_input), "1".to_string());
+        let test_input: &str = "L
+
+AAA = (BBZ, BBZ)
+BBZ = (CCC, CCC)
+CCC = (CCC, CCC)
+11A = (11B, 11B)
+11B = (11Z, 11Z)
+11Z = (11Z, 11Z)";
+        assert_eq!(part2(test_input), "No solution".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Instructions should not be empty")]
+    fn empty_instructions() {
+        part2("\n\n11A = (11Z, 11Z)\n11Z = (11A, 11A)");
+    }
+
+    #[test]
+    fn dot_export() {
+        let test_input: &str = "LR
+
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+        let (_, (_, nodes)) = parse(test_input).unwrap();
+        let network = Network::new(&nodes);
+        // Names starting with a digit are only valid DOT IDs when quoted
+        assert_eq!(
+            network.to_dot(|n| n.ends_with('A'), |n| n.ends_with('Z')),
+            r#"digraph network {
+    "11A" [style=filled, fillcolor=green];
+    "11A" -> "11B" [label=L];
+    "11A" -> "XXX" [label=R];
+    "11B" -> "XXX" [label=L];
+    "11B" -> "11Z" [label=R];
+    "11Z" [style=filled, fillcolor=red];
+    "11Z" -> "11B" [label=L];
+    "11Z" -> "XXX" [label=R];
+    "22A" [style=filled, fillcolor=green];
+    "22A" -> "22B" [label=L];
+    "22A" -> "XXX" [label=R];
+    "22B" -> "22C";
+    "22C" -> "22Z";
+    "22Z" [style=filled, fillcolor=red];
+    "22Z" -> "22B";
+    "XXX" -> "XXX";
+}"#
+        );
+    }
 }
@@ -1,74 +1,259 @@
+use std::ops::{Add, Mul};
+
+// Exact fraction, always stored with a positive denominator and in lowest terms
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    fn new(num: i128, den: i128) -> Rational {
+        let g = gcd(num, den).max(1) * den.signum();
+        Rational {
+            num: num / g,
+            den: den / g,
+        }
+    }
+
+    fn integer(num: i128) -> Rational {
+        Rational { num, den: 1 }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+
+    fn add(self, other: Rational) -> Rational {
+        Rational::new(
+            self.num * other.den + other.num * self.den,
+            self.den * other.den,
+        )
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+
+    fn mul(self, other: Rational) -> Rational {
+        Rational::new(self.num * other.num, self.den * other.den)
+    }
+}
+
+impl std::fmt::Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// Newton forward form: f(x) = sum of leading[j] * C(x, j) where leading[j] is the first
+// value of the j-th difference row and x = 0 is the first value of the history
 #[derive(Debug)]
-struct History {
-    vals: Vec<Vec<i32>>,
+struct Polynomial {
+    leading: Vec<i128>,
 }
 
-impl History {
-    fn get_next_sequence(&mut self) -> Vec<i32> {
-        let last_seq = self.vals.last().expect("Should not be empty");
-        let res = last_seq
-            .windows(2)
-            .map(|item| item[1] - item[0])
-            .collect::<Vec<i32>>();
-        self.vals.push(res.clone());
+impl Polynomial {
+    fn degree(&self) -> usize {
+        self.leading.len().saturating_sub(1)
+    }
+
+    // Value at any integer x, None if it does not fit in an i128
+    fn at(&self, x: i128) -> Option<i128> {
+        let mut binom: i128 = 1; // C(x, j), also defined for negative x
+        let mut res: i128 = 0;
+        for (j, d) in self.leading.iter().enumerate() {
+            res = res.checked_add(d.checked_mul(binom)?)?;
+            let j = j as i128;
+            binom = binom.checked_mul(x - j)? / (j + 1);
+        }
+        Some(res)
+    }
+
+    // Coefficients of x^0, x^1, .. x^degree. The zero polynomial is [0]
+    fn coefficients(&self) -> Vec<Rational> {
+        let mut res = vec![Rational::integer(0); self.leading.len().max(1)];
+        let mut falling = vec![1]; // x(x-1)..(x-j+1) as integer coefficients
+        let mut factorial: i128 = 1;
+        for (j, d) in self.leading.iter().enumerate() {
+            if j > 0 {
+                factorial *= j as i128;
+            }
+            let scale = Rational::new(*d, factorial);
+            falling.iter().enumerate().for_each(|(i, c)| {
+                res[i] = res[i] + scale * Rational::integer(*c);
+            });
+            // Multiply by (x - j) for the next term
+            let mut next = vec![0; falling.len() + 1];
+            falling.iter().enumerate().for_each(|(i, c)| {
+                next[i + 1] += c;
+                next[i] -= c * j as i128;
+            });
+            falling = next;
+        }
         res
     }
+}
+
+#[derive(Debug, PartialEq)]
+enum FitError {
+    // One value, or two different values, never reach a zero row but are not enough to rule
+    // out a polynomial either
+    TooShort,
+    // The differences never reach an all-zero row within the history. best_fit_degree is the
+    // degree whose next difference row is closest to zero and residual is that row's largest value
+    NotPolynomial {
+        best_fit_degree: usize,
+        residual: i128,
+    },
+    Overflow,
+}
 
-    fn process(&mut self) {
-        let mut last_seq = self.vals.last_mut().expect("Should not be empty").clone();
-        while !last_seq.iter().all(|&x| x == 0) {
-            last_seq = self.get_next_sequence();
+impl std::fmt::Display for FitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FitError::TooShort => write!(f, "too short to tell"),
+            FitError::NotPolynomial {
+                best_fit_degree,
+                residual,
+            } => write!(
+                f,
+                "not a polynomial (best fit degree {}, residual {})",
+                best_fit_degree, residual
+            ),
+            FitError::Overflow => write!(f, "value does not fit in an i128"),
         }
+    }
+}
 
-        let mut curr = 0;
-        self.vals.last_mut().unwrap().push(curr);
-        self.vals = self
-            .vals
-            .iter()
-            .rev()
-            .map(|v| {
-                let first_item = *v.first().expect("Should not be empty");
-                let mut res = v.clone();
-                curr = first_item - curr;
-                res.insert(0, curr);
-                res
-            })
-            .collect::<Vec<Vec<i32>>>()
-            .iter()
-            .rev()
-            .map(|v| v.clone())
-            .collect();
+#[derive(Debug)]
+struct History {
+    line: usize, // 1-indexed line in the input
+    vals: Vec<i64>,
+}
+
+impl History {
+    // Take differences until a row is all zero. A degree d polynomial needs at least d + 2
+    // values for that zero row to be seen, otherwise the history is not known to be polynomial
+    fn fit(&self) -> Result<Polynomial, FitError> {
+        let mut row = self.vals.iter().map(|v| *v as i128).collect::<Vec<i128>>();
+        let mut leading = Vec::new();
+        let mut best: Option<(usize, i128)> = None; // (degree, residual)
+        while !row.is_empty() {
+            if row.iter().all(|&x| x == 0) {
+                return Ok(Polynomial { leading });
+            }
+            if !leading.is_empty() {
+                let residual = row.iter().map(|x| x.abs()).max().unwrap();
+                if best.is_none_or(|(_, r)| residual < r) {
+                    best = Some((leading.len() - 1, residual));
+                }
+            }
+            leading.push(row[0]);
+            row = row.windows(2).map(|item| item[1] - item[0]).collect();
+        }
+        match best {
+            Some((best_fit_degree, residual)) if self.vals.len() > 2 => {
+                Err(FitError::NotPolynomial {
+                    best_fit_degree,
+                    residual,
+                })
+            }
+            _ => Err(FitError::TooShort),
+        }
     }
 
-    fn get_extrapolated(&self) -> i32 {
-        *self.vals[0].first().expect("Should not be empty")
+    // Value `steps` before the first one in the history
+    fn extrapolate(&self, steps: i128) -> Result<i128, FitError> {
+        self.fit()?.at(-steps).ok_or(FitError::Overflow)
     }
 }
 
 fn main() {
     let input: &str = include_str!("./input.txt");
-    let result: String = part2(input);
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    if args.iter().any(|a| a == "--fit") {
+        println!("{}", report(input));
+        return;
+    }
+    let result: String = match args.iter().find_map(|a| a.strip_prefix("--offset=")) {
+        Some(steps) => match extrapolate(input, steps.parse().expect("Invalid offset")) {
+            Ok(result) => result,
+            Err((line, e)) => {
+                eprintln!("Line {}: {}", line, e);
+                return;
+            }
+        },
+        None => part2(input),
+    };
     println!("Result: {}", result);
 }
 
 fn part2(input: &str) -> String {
-    let mut seqs = parse(input);
-    seqs.iter_mut().for_each(|seq| seq.process());
-    seqs.iter()
-        .map(|seq| seq.get_extrapolated())
-        .sum::<i32>()
-        .to_string()
+    extrapolate(input, 1).unwrap_or_else(|(line, e)| panic!("Line {}: {}", line, e))
+}
+
+// Sum of every history extrapolated by steps, or the first line that cannot be
+fn extrapolate(input: &str, steps: i128) -> Result<String, (usize, FitError)> {
+    let seqs = parse(input);
+    let mut sum: i128 = 0;
+    for seq in seqs.iter() {
+        sum += seq.extrapolate(steps).map_err(|e| (seq.line, e))?;
+    }
+    Ok(sum.to_string())
+}
+
+// One line per history with its fit or why it could not be fitted, then a summary
+fn report(input: &str) -> String {
+    let seqs = parse(input);
+    let mut lines = seqs
+        .iter()
+        .map(|seq| match seq.fit() {
+            Ok(poly) => {
+                let coefficients = poly
+                    .coefficients()
+                    .iter()
+                    .map(|c| c.to_string())
+                    .collect::<Vec<String>>();
+                format!(
+                    "{}: ok degree {} coefficients [{}]",
+                    seq.line,
+                    poly.degree(),
+                    coefficients.join(", ")
+                )
+            }
+            Err(e) => format!("{}: {}", seq.line, e),
+        })
+        .collect::<Vec<String>>();
+    let ok = seqs.iter().filter(|seq| seq.fit().is_ok()).count();
+    lines.push(format!("{} of {} lines are well-formed", ok, seqs.len()));
+    lines.join("\n")
 }
 
+// Blank lines are skipped but every history keeps its own line number
 fn parse(input: &str) -> Vec<History> {
     input
         .lines()
-        .map(|line| {
-            let vals: Vec<i32> = line
-                .split(" ")
-                .map(|val| val.parse::<i32>().unwrap())
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let vals: Vec<i64> = line
+                .split_whitespace()
+                .map(|val| val.parse::<i64>().unwrap())
                 .collect();
-            History { vals: vec![vals] }
+            History { line: i + 1, vals }
         })
         .collect::<Vec<History>>()
 }
@@ -85,4 +270,77 @@ mod tests {
         let result: String = part2(test_input);
         assert_eq!(result, "2".to_string());
     }
+
+    #[test]
+    fn polynomial_fit() {
+        let seqs = parse("1 3 6 10 15 21\n10 13 16 21 30 45");
+        let triangle = seqs[0].fit().unwrap();
+        assert_eq!(triangle.degree(), 2);
+        assert_eq!(
+            triangle.coefficients(),
+            vec![
+                Rational::new(1, 1),
+                Rational::new(3, 2),
+                Rational::new(1, 2)
+            ]
+        );
+        // f(x) = (x + 1)(x + 2) / 2
+        assert_eq!(triangle.at(1000), Some(501501));
+        assert_eq!(seqs[0].extrapolate(1), Ok(0));
+        assert_eq!(seqs[0].extrapolate(3), Ok(1));
+        assert_eq!(seqs[1].fit().unwrap().degree(), 3);
+        assert_eq!(seqs[1].extrapolate(1), Ok(5));
+    }
+
+    #[test]
+    fn non_polynomial() {
+        let test_input: &str = "1 2 4 8 16 32
+0 3 6 9 12 15
+5";
+        let seqs = parse(test_input);
+        assert_eq!(
+            seqs[0].fit().unwrap_err(),
+            FitError::NotPolynomial {
+                best_fit_degree: 4,
+                residual: 1
+            }
+        );
+        // A single value could be any polynomial, and so could two
+        assert_eq!(seqs[2].fit().unwrap_err(), FitError::TooShort);
+        assert_eq!(parse("1 2")[0].fit().unwrap_err(), FitError::TooShort);
+        assert_eq!(parse("5 5")[0].fit().unwrap().degree(), 0);
+        assert_eq!(
+            extrapolate(test_input, 1),
+            Err((
+                1,
+                FitError::NotPolynomial {
+                    best_fit_degree: 4,
+                    residual: 1
+                }
+            ))
+        );
+        assert_eq!(
+            report(test_input),
+            "1: not a polynomial (best fit degree 4, residual 1)
+2: ok degree 1 coefficients [0, 3]
+3: too short to tell
+1 of 3 lines are well-formed"
+        );
+    }
+
+    #[test]
+    fn blank_lines() {
+        let test_input: &str = "0 3 6 9
+
+5
+0 0 0
+";
+        assert_eq!(
+            report(test_input),
+            "1: ok degree 1 coefficients [0, 3]
+3: too short to tell
+4: ok degree 0 coefficients [0]
+2 of 3 lines are well-formed"
+        );
+    }
 }